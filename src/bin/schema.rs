@@ -1,5 +1,8 @@
-use cosmwasm_schema::write_api;
+use std::env::current_dir;
 
+use cosmwasm_schema::{export_schema, schema_for, write_api};
+
+use palomagold_aave_migrator_cw::event::{ContractEvent, HookMsg};
 use palomagold_aave_migrator_cw::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
 fn main() {
@@ -8,4 +11,9 @@ fn main() {
         execute: ExecuteMsg,
         query: QueryMsg,
     }
+
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    export_schema(&schema_for!(ContractEvent), &out_dir);
+    export_schema(&schema_for!(HookMsg), &out_dir);
 }
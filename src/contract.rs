@@ -1,9 +1,11 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
 use cw2::set_contract_version;
+use semver::Version;
 
 use crate::error::ContractError;
+use crate::event::ContractEvent;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, PalomaMsg, QueryMsg};
 use crate::state::{State, STATE};
 
@@ -11,12 +13,58 @@ use crate::state::{State, STATE};
 const CONTRACT_NAME: &str = "crates.io:palomagold-aave-migrator-cw";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// delay between scheduling and executing an emergency withdrawal
+const EMERGENCY_WITHDRAW_DELAY: u64 = 2 * 24 * 60 * 60;
+
+// legacy withdraw timestamps converted per migration or conversion message by default
+const LEGACY_CONVERSION_BATCH: u32 = 500;
+
+// reply ID of hook deliveries, which only reply on error
+const HOOK_REPLY_ID: u64 = 1;
+
+// gas available to a single hook delivery
+const HOOK_GAS_LIMIT: u64 = 500_000;
+
+// subscribers per hook event
+const MAX_HOOKS: u32 = 10;
+
+// largest Paloma-side fee on bridges and deposits, in basis points
+const MAX_PALOMA_FEE_BPS: u16 = 1_000;
+
+// scale of the service fee sent through `update_service_fee`, following the
+// `DENOMINATOR: constant(uint256) = 10 ** 18` convention of Paloma's Vyper
+// contracts, which take `amount * service_fee / DENOMINATOR`; quotes are only
+// right for EVM contracts using this scale
+const SERVICE_FEE_DENOMINATOR: u128 = 1_000_000_000_000_000_000;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = cw2::get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            contract: stored.contract,
+        });
+    }
+    let from = Version::parse(&stored.version)?;
+    if from > Version::parse(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    let steps = migration::run(deps.branch(), &env, &from, &msg)?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(Response::new()
         .add_attribute("action", "migrate")
-        .add_attribute("version", CONTRACT_VERSION))
+        .add_attribute("from_version", stored.version.clone())
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("steps", steps.join(","))
+        .add_event(ContractEvent::Migrated {
+            from_version: stored.version,
+            version: CONTRACT_VERSION.to_string(),
+            steps: steps.into_iter().map(str::to_string).collect(),
+        }))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -35,7 +83,11 @@ pub fn instantiate(
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender)
+        .add_event(ContractEvent::Instantiated {
+            owner: state.owner,
+            palomagold_denom: state.palomagold_denom,
+        }))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -45,23 +97,133 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<PalomaMsg>, ContractError> {
+    execute::check_funds(deps.as_ref(), &info, msg.funds_policy())?;
     match msg {
         ExecuteMsg::RegisterChain {
             chain_id,
             chain_setting,
-        } => execute::register_chain(deps, info, chain_id, chain_setting),
+            create_job,
+        } => execute::register_chain(deps, info, chain_id, chain_setting, create_job),
         ExecuteMsg::SendPalomaGold {
             chain_id,
             recipient,
             amount,
-        } => execute::send_paloma_gold(deps, info, chain_id, recipient, amount),
+            token,
+        } => execute::send_paloma_gold(deps, env, info, chain_id, recipient, amount, token),
         ExecuteMsg::Release {
             chain_id,
             recipient,
             amount,
             nonce,
-        } => execute::release(deps, env, info, chain_id, recipient, amount, nonce),
+            token,
+        } => execute::release(deps, env, info, chain_id, recipient, amount, nonce, token),
+        ExecuteMsg::Deposit { chain_id } => execute::deposit(deps, env, info, chain_id),
+        ExecuteMsg::AddToDenylist {
+            evm_addresses,
+            paloma_addresses,
+        } => execute::add_to_denylist(deps, info, evm_addresses, paloma_addresses),
+        ExecuteMsg::RemoveFromDenylist {
+            evm_addresses,
+            paloma_addresses,
+        } => execute::remove_from_denylist(deps, info, evm_addresses, paloma_addresses),
+        ExecuteMsg::SetSupplyAllowance { token, allowance } => {
+            execute::set_supply_allowance(deps, info, token, allowance)
+        }
+        ExecuteMsg::RegisterToken { token_key, token } => {
+            execute::register_token(deps, info, token_key, token)
+        }
+        ExecuteMsg::SetConversionRatio {
+            chain_id,
+            epoch,
+            conversion_ratio,
+        } => execute::set_conversion_ratio(deps, info, chain_id, epoch, conversion_ratio),
+        ExecuteMsg::ReleaseFromAave {
+            chain_id,
+            recipient,
+            aave_amount,
+            nonce,
+            epoch,
+        } => execute::release_from_aave(
+            deps,
+            env,
+            info,
+            chain_id,
+            recipient,
+            aave_amount,
+            nonce,
+            epoch,
+        ),
+        ExecuteMsg::BatchRelease { chain_id, releases } => {
+            execute::batch_release(deps, env, info, chain_id, releases)
+        }
         ExecuteMsg::CancelTx { transaction_id } => execute::cancel_tx(deps, info, transaction_id),
+        ExecuteMsg::EmergencyWithdraw { denom, amount, to } => {
+            execute::emergency_withdraw(deps, env, info, denom, amount, to)
+        }
+        ExecuteMsg::ExecuteEmergencyWithdraw {} => {
+            execute::execute_emergency_withdraw(deps, env, info)
+        }
+        ExecuteMsg::CancelEmergencyWithdraw {} => execute::cancel_emergency_withdraw(deps, info),
+        ExecuteMsg::SweepStrayFunds { denom, to } => {
+            execute::sweep_stray_funds(deps, env, info, denom, to)
+        }
+        ExecuteMsg::CancelRelease {
+            chain_id,
+            nonce,
+            forward,
+        } => execute::cancel_release(deps, info, chain_id, nonce, forward),
+        ExecuteMsg::ConvertLegacyReleases { limit } => {
+            execute::convert_legacy_releases(deps, info, limit)
+        }
+        ExecuteMsg::BackfillReleases { releases } => {
+            execute::backfill_releases(deps, info, releases)
+        }
+        ExecuteMsg::ImportState { records } => execute::import_state(deps, info, records),
+        ExecuteMsg::Seal {} => execute::seal(deps, info),
+        ExecuteMsg::ConfirmReleases { chain_id, nonces } => {
+            execute::confirm_releases(deps, info, chain_id, nonces)
+        }
+        ExecuteMsg::SettleSkywaySends { token, amount } => {
+            execute::settle_skyway_sends(deps, info, token, amount)
+        }
+        ExecuteMsg::CreateVesting {
+            chain_id,
+            recipient,
+            start,
+            cliff,
+            duration,
+            total,
+        } => execute::create_vesting(
+            deps, info, chain_id, recipient, start, cliff, duration, total,
+        ),
+        ExecuteMsg::ReleaseVested {
+            chain_id,
+            recipient,
+        } => execute::release_vested(deps, env, chain_id, recipient),
+        ExecuteMsg::AnnounceJobRotation {
+            chain_id,
+            new_job_id,
+            overlap,
+            create_job,
+        } => execute::announce_job_rotation(
+            deps, env, info, chain_id, new_job_id, overlap, create_job,
+        ),
+        ExecuteMsg::FinalizeJobRotation { chain_id } => {
+            execute::finalize_job_rotation(deps, env, info, chain_id)
+        }
+        ExecuteMsg::CancelJobRotation { chain_id } => {
+            execute::cancel_job_rotation(deps, info, chain_id)
+        }
+        ExecuteMsg::AddHook { event, contract } => execute::add_hook(deps, info, event, contract),
+        ExecuteMsg::RemoveHook { event, contract } => {
+            execute::remove_hook(deps, info, event, contract)
+        }
+        ExecuteMsg::SetMigrationWindow { chain_id, window } => {
+            execute::set_migration_window(deps, env, info, chain_id, window)
+        }
+        ExecuteMsg::SyncMigrationWindow { chain_id } => {
+            execute::sync_migration_window(deps, env, chain_id)
+        }
         ExecuteMsg::SetPaloma { chain_id } => execute::set_paloma(deps, info, chain_id),
         ExecuteMsg::UpdateRefundWallet {
             chain_id,
@@ -79,49 +241,470 @@ pub fn execute(
             chain_id,
             new_service_fee,
         } => execute::update_service_fee(deps, info, chain_id, new_service_fee),
+        ExecuteMsg::BroadcastServiceFeeCollector {
+            chains,
+            new_service_fee_collector,
+        } => {
+            execute::broadcast_service_fee_collector(deps, info, chains, new_service_fee_collector)
+        }
+        ExecuteMsg::BroadcastServiceFee {
+            chains,
+            new_service_fee,
+        } => execute::broadcast_service_fee(deps, info, chains, new_service_fee),
+        ExecuteMsg::SetFeeBounds {
+            chain_id,
+            fee,
+            bounds,
+        } => execute::set_fee_bounds(deps, info, chain_id, fee, bounds),
+        ExecuteMsg::SetFeeOverrideApprover { approver } => {
+            execute::set_fee_override_approver(deps, info, approver)
+        }
+        ExecuteMsg::ApproveFeeOverride {
+            chain_id,
+            fee,
+            value,
+        } => execute::approve_fee_override(deps, info, chain_id, fee, value),
+        ExecuteMsg::SetPalomaFee { bps, collector } => {
+            execute::set_paloma_fee(deps, info, bps, collector)
+        }
+        ExecuteMsg::ClaimFees {} => execute::claim_fees(deps, info),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        HOOK_REPLY_ID => execute::hook_failed(msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+pub mod migration {
+    use super::*;
+    use crate::state::{
+        releases, LegacyConversion, ReleaseRecord, CHAIN_SETTINGS, LEGACY_CONVERSION, SEALED,
+        WITHDRAW_TIMESTAMP,
+    };
+    use cosmwasm_std::{Order, Storage};
+    use cw_storage_plus::Bound;
+
+    type Step = fn(DepsMut, &Env, &MigrateMsg) -> Result<(), ContractError>;
+
+    /// Storage migrations in version order. A step runs when upgrading from
+    /// a version older than its own.
+    const STEPS: &[(&str, Step)] = &[("0.2.0", v0_2_0)];
+
+    /// Runs the steps needed to upgrade from `from` and returns their versions.
+    pub fn run(
+        mut deps: DepsMut,
+        env: &Env,
+        from: &Version,
+        msg: &MigrateMsg,
+    ) -> Result<Vec<&'static str>, ContractError> {
+        let mut applied = vec![];
+        for (version, step) in STEPS {
+            if *from < Version::parse(version)? {
+                step(deps.branch(), env, msg)?;
+                applied.push(*version);
+            }
+        }
+        Ok(applied)
+    }
+
+    /// 0.1 stored only the job ID of a chain. The new settings are written
+    /// out with their defaults, or with the TTL given in the migrate message.
+    /// 0.1 kept only the time of each release; conversion of those entries
+    /// into legacy release records starts here and continues with
+    /// `ConvertLegacyReleases`. Live deployments are sealed, so only fresh
+    /// instances accept `ImportState`.
+    fn v0_2_0(deps: DepsMut, _env: &Env, msg: &MigrateMsg) -> Result<(), ContractError> {
+        let chain_ids = CHAIN_SETTINGS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for chain_id in chain_ids {
+            let mut chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+            if chain_setting.release_ttl.is_none() {
+                chain_setting.release_ttl = msg.release_ttl;
+            }
+            CHAIN_SETTINGS.save(deps.storage, chain_id, &chain_setting)?;
+        }
+
+        SEALED.save(deps.storage, &true)?;
+        LEGACY_CONVERSION.save(deps.storage, &LegacyConversion::default())?;
+        convert_legacy_releases(
+            deps.storage,
+            msg.legacy_batch.unwrap_or(LEGACY_CONVERSION_BATCH),
+        )?;
+        Ok(())
+    }
+
+    /// Converts up to `limit` further `WITHDRAW_TIMESTAMP` entries into legacy
+    /// release records. Nonces that already have a record are skipped.
+    pub fn convert_legacy_releases(
+        storage: &mut dyn Storage,
+        limit: u32,
+    ) -> Result<LegacyConversion, ContractError> {
+        let mut conversion = LEGACY_CONVERSION.load(storage)?;
+        let entries = WITHDRAW_TIMESTAMP
+            .range(
+                storage,
+                conversion.last.clone().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit as usize)
+            .collect::<StdResult<Vec<_>>>()?;
+        conversion.done = entries.len() < limit as usize;
+        for (key, timestamp) in entries {
+            if !releases().has(storage, key.clone()) {
+                releases().save(
+                    storage,
+                    key.clone(),
+                    &ReleaseRecord::legacy(key.0.clone(), timestamp),
+                )?;
+                conversion.converted += 1;
+            }
+            conversion.last = Some(key);
+        }
+        LEGACY_CONVERSION.save(storage, &conversion)?;
+        Ok(conversion)
     }
 }
 
 pub mod execute {
-    use cosmwasm_std::{Coin, CosmosMsg, Uint128, Uint256};
+    use cosmwasm_std::{
+        from_json, to_json_binary, BankMsg, Coin, CosmosMsg, Decimal256, Empty, Event, Order,
+        Storage, SubMsg, Timestamp, Uint128, Uint256, WasmMsg,
+    };
     use ethabi::{Address, Contract, Function, Param, ParamType, StateMutability, Token, Uint};
     use std::collections::BTreeMap;
     use std::str::FromStr;
 
     use super::*;
     use crate::{
-        msg::{CancelTx, ExecuteJob, SendTx},
-        state::{ChainSetting, CHAIN_SETTINGS, WITHDRAW_TIMESTAMP},
+        event::{HookDelivery, HookMsg},
+        msg::{
+            BackfillItem, CancelTx, ChainTargets, CreateJob, ExecuteJob, FundsPolicy,
+            JobDefinition, NewJob, ReleaseItem, SendTx, StateRecord,
+        },
+        state::{
+            releases, ChainSetting, ConversionRatio, EmergencyWithdrawal, EvmToken, FeeBounds,
+            FeeKind, HookEvent, JobPurpose, JobRotation, MigrationWindow, PalomaFee, ReleaseRecord,
+            ReleaseStatus, TokenInfo, VestingSchedule, WindowStatus, ACCRUED_FEES, CHAIN_SETTINGS,
+            CONTRACT_NONCE, CONVERSION_RATIOS, CURRENT_EPOCH, DENIED_EVM_ADDRESSES,
+            DENIED_PALOMA_ADDRESSES, FEE_BOUNDS, FEE_OVERRIDES, FEE_OVERRIDE_APPROVER,
+            FEE_PAID_DEPOSITS, FORWARDED_FEES, HOOKS, JOB_ROTATIONS, LEGACY_CONVERSION,
+            MIGRATION_WINDOWS, PALOMA_FEE, PENDING_EMERGENCY_WITHDRAWAL, RECIPIENT_TOTALS,
+            RELEASED_PER_CHAIN, RESERVED_RELEASES, SEALED, SKYWAY_COMMITTED, SUPPLY_ALLOWANCE,
+            SUPPLY_TOTALS, TOKENS, VESTING_SCHEDULES, WINDOW_STATUS, WITHDRAW_TIMESTAMP,
+        },
     };
 
+    /// Rejects funds that the funds policy of a message does not accept, so
+    /// they are never silently absorbed by the contract.
+    pub fn check_funds(
+        deps: Deps,
+        info: &MessageInfo,
+        policy: FundsPolicy,
+    ) -> Result<(), ContractError> {
+        match policy {
+            FundsPolicy::None => {
+                if !info.funds.is_empty() {
+                    return Err(ContractError::InvalidFunds {
+                        reason: "this message does not accept funds".to_string(),
+                    });
+                }
+            }
+            FundsPolicy::Palomagold => {
+                let denom = STATE.load(deps.storage)?.palomagold_denom;
+                match info.funds.as_slice() {
+                    [coin] if !coin.amount.is_zero() && is_bridged_denom(deps, &denom, coin)? => {}
+                    _ => {
+                        return Err(ContractError::InvalidFunds {
+                            reason: format!("expected a single {} or registered token coin", denom),
+                        })
+                    }
+                }
+            }
+            FundsPolicy::Any => {}
+        }
+        Ok(())
+    }
+
+    fn is_bridged_denom(deps: Deps, denom: &str, coin: &Coin) -> StdResult<bool> {
+        if coin.denom == denom {
+            return Ok(true);
+        }
+        for token in TOKENS.range(deps.storage, None, None, Order::Ascending) {
+            if token?.1.denom == coin.denom {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn register_token(
+        deps: DepsMut,
+        info: MessageInfo,
+        token_key: String,
+        token: TokenInfo,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        // Accounting is keyed by denom, so the denom of a token is fixed once registered.
+        if let Some(existing) = TOKENS.may_load(deps.storage, token_key.clone())? {
+            if existing.denom != token.denom {
+                return Err(ContractError::TokenDenomChanged {});
+            }
+        }
+        let mut token = token;
+        for evm_token in token.chains.values_mut() {
+            evm_token.address = parse_evm_address(&evm_token.address)?.1;
+        }
+        TOKENS.save(deps.storage, token_key.clone(), &token)?;
+        Ok(Response::new()
+            .add_attribute("action", "register_token")
+            .add_attribute("token_key", token_key.clone())
+            .add_attribute("denom", token.denom.clone())
+            .add_event(ContractEvent::TokenRegistered {
+                token_key,
+                denom: token.denom,
+                chain_ids: token.chains.into_keys().collect(),
+            }))
+    }
+
     pub fn register_chain(
         deps: DepsMut,
         info: MessageInfo,
         chain_id: String,
         chain_setting: ChainSetting,
+        create_job: Option<NewJob>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
         let state = STATE.load(deps.storage)?;
         if info.sender != state.owner {
             return Err(ContractError::Unauthorized {});
         }
+        let jobs = &chain_setting.jobs;
+        for job_id in [&jobs.release, &jobs.admin, &jobs.fee] {
+            if job_id.as_deref() == Some("") {
+                return Err(ContractError::InvalidJob {
+                    reason: "job_id is empty".to_string(),
+                });
+            }
+        }
         CHAIN_SETTINGS.save(deps.storage, chain_id.clone(), &chain_setting)?;
-        Ok(Response::new()
+        let job_created = create_job.is_some();
+        let mut response = Response::new()
             .add_attribute("action", "register_chain")
-            .add_attribute("chain_id", chain_id))
+            .add_attribute("chain_id", chain_id.clone());
+        if let Some(job) = create_job {
+            response = response.add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: Some(job_definition(&chain_id, &chain_setting.job_id, job)?),
+                execute_job: None,
+            }));
+        }
+        Ok(response.add_event(ContractEvent::ChainRegistered {
+            chain_id,
+            job_id: chain_setting.job_id,
+            batch_release: chain_setting.batch_release,
+            recipient_cap: chain_setting.recipient_cap,
+            release_ttl: chain_setting.release_ttl,
+            jobs: chain_setting.jobs,
+            job_created,
+        }))
     }
 
-    pub fn send_paloma_gold(
+    pub fn announce_job_rotation(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
+        new_job_id: String,
+        overlap: u64,
+        create_job: Option<NewJob>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        if JOB_ROTATIONS.has(deps.storage, chain_id.clone()) {
+            return Err(ContractError::JobRotationPending {});
+        }
+        let old_job_id = chain_setting.job(JobPurpose::Release).to_string();
+        if new_job_id.is_empty() || new_job_id == old_job_id {
+            return Err(ContractError::InvalidJob {
+                reason: "new job_id must be set and differ from the current one".to_string(),
+            });
+        }
+        // Retries on the old job are accepted for as long as releases can be retried.
+        if let Some(ttl) = chain_setting.release_ttl {
+            if overlap < ttl {
+                return Err(ContractError::RotationTooShort { min: ttl });
+            }
+        }
+        let rotation = JobRotation {
+            old_job_id,
+            new_job_id,
+            announced_at: env.block.time,
+            finalize_at: env.block.time.plus_seconds(overlap),
+        };
+        JOB_ROTATIONS.save(deps.storage, chain_id.clone(), &rotation)?;
+        let mut response = Response::new()
+            .add_attribute("action", "announce_job_rotation")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_attribute("new_job_id", rotation.new_job_id.clone());
+        if let Some(job) = create_job {
+            response = response.add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: Some(job_definition(&chain_id, &rotation.new_job_id, job)?),
+                execute_job: None,
+            }));
+        }
+        Ok(response.add_event(ContractEvent::JobRotationAnnounced {
+            chain_id,
+            old_job_id: rotation.old_job_id,
+            new_job_id: rotation.new_job_id,
+            finalize_at: rotation.finalize_at,
+        }))
+    }
+
+    pub fn finalize_job_rotation(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let rotation = JOB_ROTATIONS
+            .may_load(deps.storage, chain_id.clone())?
+            .ok_or(ContractError::NoJobRotation {})?;
+        if env.block.time < rotation.finalize_at {
+            return Err(ContractError::Timelocked {
+                executable_at: rotation.finalize_at,
+            });
+        }
+        let mut chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        chain_setting.jobs.release = Some(rotation.new_job_id.clone());
+        CHAIN_SETTINGS.save(deps.storage, chain_id.clone(), &chain_setting)?;
+        JOB_ROTATIONS.remove(deps.storage, chain_id.clone());
+        Ok(Response::new()
+            .add_attribute("action", "finalize_job_rotation")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_attribute("job_id", rotation.new_job_id.clone())
+            .add_event(ContractEvent::JobRotationFinalized {
+                chain_id,
+                old_job_id: rotation.old_job_id,
+                new_job_id: rotation.new_job_id,
+            }))
+    }
+
+    pub fn cancel_job_rotation(
         deps: DepsMut,
         info: MessageInfo,
         chain_id: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let rotation = JOB_ROTATIONS
+            .may_load(deps.storage, chain_id.clone())?
+            .ok_or(ContractError::NoJobRotation {})?;
+        JOB_ROTATIONS.remove(deps.storage, chain_id.clone());
+        Ok(Response::new()
+            .add_attribute("action", "cancel_job_rotation")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_event(ContractEvent::JobRotationCancelled {
+                chain_id,
+                old_job_id: rotation.old_job_id,
+                new_job_id: rotation.new_job_id,
+            }))
+    }
+
+    /// Builds the scheduler job of a chain, refusing targets and ABIs that
+    /// would only fail once the first job is executed.
+    fn job_definition(
+        chain_id: &str,
+        job_id: &str,
+        job: NewJob,
+    ) -> Result<CreateJob, ContractError> {
+        if job_id.is_empty() {
+            return Err(ContractError::InvalidJob {
+                reason: "job_id is empty".to_string(),
+            });
+        }
+        let (_, address) = parse_evm_address(&job.address)?;
+        Contract::load(job.abi.as_bytes()).map_err(|err| ContractError::InvalidJob {
+            reason: format!("invalid ABI: {err}"),
+        })?;
+        Ok(CreateJob {
+            job_id: job_id.to_string(),
+            chain_type: job.chain_type.unwrap_or_else(|| "evm".to_string()),
+            chain_reference_id: chain_id.to_string(),
+            definition: JobDefinition {
+                abi: job.abi,
+                address,
+            },
+            // Every execution supplies its own payload.
+            payload: Binary::default(),
+            payload_modifiable: true,
+            is_mev: job.is_mev,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_paloma_gold(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
         recipient: String,
         amount: Uint128,
+        token: Option<String>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
         // Implement the logic for sending Paloma Gold
         let state = STATE.load(deps.storage)?;
         assert!(info.sender == state.owner, "Unauthorized");
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, false)?;
+        let (_, canonical_recipient) = parse_evm_address(&recipient)?;
+        check_not_denied(deps.storage, &canonical_recipient)?;
+        if let Some(token_key) = token.as_deref() {
+            // Registered tokens can only be bridged to chains they are deployed on.
+            evm_token(deps.storage, token_key, &chain_id)?;
+        }
+        let denom = token_denom(deps.storage, token.as_deref())?;
+        check_unclaimed_fees(deps.as_ref(), &env, &denom, amount)?;
+        // Deposits paid the fee when they came in; only the rest is charged.
+        let fee_paid = use_fee_paid_deposits(deps.storage, &denom, amount)?;
+        let (charged, fee_event) = charge_fee(deps.storage, &denom, amount - fee_paid)?;
+        let amount = fee_paid + charged;
+        let totals_key = (chain_id.clone(), canonical_recipient.clone(), denom.clone());
+        let mut totals = RECIPIENT_TOTALS
+            .may_load(deps.storage, totals_key.clone())?
+            .unwrap_or_default();
+        totals.bridged += amount;
+        RECIPIENT_TOTALS.save(deps.storage, totals_key, &totals)?;
+        let mut supply = SUPPLY_TOTALS
+            .may_load(deps.storage, denom.clone())?
+            .unwrap_or_default();
+        supply.bridged += amount;
+        SUPPLY_TOTALS.save(deps.storage, denom.clone(), &supply)?;
+        SKYWAY_COMMITTED.update(deps.storage, denom.clone(), |committed| -> StdResult<_> {
+            Ok(committed.unwrap_or_default() + amount)
+        })?;
         let coin_to_bridge: Coin = Coin {
-            denom: state.palomagold_denom.clone(),
+            denom: denom.clone(),
+            amount,
+        };
+        let event = ContractEvent::BridgeSent {
+            chain_id: chain_id.clone(),
+            recipient: canonical_recipient,
+            denom,
             amount,
         };
         Ok(Response::new()
@@ -133,372 +716,5221 @@ pub mod execute {
                 }),
                 cancel_tx: None,
             }))
+            .add_events(window_event)
+            .add_events(fee_event)
+            .add_submessages(hook_messages(deps.storage, &event)?)
+            .add_event(event)
             .add_attribute("action", "send_paloma_gold"))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn release(
-        deps: DepsMut,
+        mut deps: DepsMut,
         env: Env,
         info: MessageInfo,
         chain_id: String,
         recipient: String,
         amount: Uint256,
         nonce: Uint256,
+        token: Option<String>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
         // Implement the logic for releasing funds
         let state = STATE.load(deps.storage)?;
         assert!(info.sender == state.owner, "Unauthorized");
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, true)?;
 
-        if let Some(timestamp) =
-            WITHDRAW_TIMESTAMP.may_load(deps.storage, (chain_id.clone(), nonce.to_string()))?
-        {
-            if timestamp.plus_seconds(60).gt(&env.block.time) {
-                // If the timestamp is not older than 60 seconds, return an error
-                return Err(ContractError::Pending {});
-            }
-        }
-
-        let recipient_address: Address = Address::from_str(recipient.as_str()).unwrap();
-        let amount: Uint = Uint::from_big_endian(&amount.to_be_bytes());
-        let nonce: Uint = Uint::from_big_endian(&nonce.to_be_bytes());
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "release".to_string(),
-                vec![Function {
-                    name: "release".to_string(),
-                    inputs: vec![
-                        Param {
-                            name: "recipient".to_string(),
-                            kind: ParamType::Address,
-                            internal_type: None,
-                        },
-                        Param {
-                            name: "amount".to_string(),
-                            kind: ParamType::Uint(256),
-                            internal_type: None,
-                        },
-                        Param {
-                            name: "nonce".to_string(),
-                            kind: ParamType::Uint(256),
-                            internal_type: None,
-                        },
-                    ],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
-        };
-
-        WITHDRAW_TIMESTAMP.save(
-            deps.storage,
-            (chain_id.clone(), nonce.to_string()),
-            &env.block.time,
+        let prepared = prepare_release(
+            deps.branch(),
+            &env,
+            &chain_id,
+            &recipient,
+            amount,
+            nonce,
+            token.as_deref(),
+            false,
         )?;
 
+        let event = prepared.scheduled_event();
         Ok(Response::new()
             .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("release")
-                            .unwrap()
-                            .encode_input(&[
-                                Token::Address(recipient_address),
-                                Token::Uint(amount),
-                                Token::Uint(nonce),
-                            ])
-                            .unwrap(),
-                    ),
-                },
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: prepared.job_id.clone(),
+                    payload: release_payload(&prepared),
+                }),
             }))
+            .add_events(window_event)
+            .add_submessages(hook_messages(deps.storage, &event)?)
+            .add_event(event)
             .add_attribute("action", "release"))
     }
 
-    pub fn cancel_tx(
+    pub fn deposit(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        if DENIED_PALOMA_ADDRESSES.has(deps.storage, &info.sender) {
+            return Err(ContractError::Denied {
+                address: info.sender.to_string(),
+            });
+        }
+        if !CHAIN_SETTINGS.has(deps.storage, chain_id.clone()) {
+            return Err(ContractError::UnknownChain { chain_id });
+        }
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, false)?;
+        // The funds policy of `Deposit` guarantees a single non-zero coin of a bridged denom.
+        let Coin { denom, amount } = info.funds[0].clone();
+        let mut supply = SUPPLY_TOTALS
+            .may_load(deps.storage, denom.clone())?
+            .unwrap_or_default();
+        supply.received += amount;
+        SUPPLY_TOTALS.save(deps.storage, denom.clone(), &supply)?;
+        let (fee_paid, fee_event) = charge_fee(deps.storage, &denom, amount)?;
+        FEE_PAID_DEPOSITS.update(deps.storage, denom.clone(), |deposits| -> StdResult<_> {
+            Ok(deposits.unwrap_or_default() + fee_paid)
+        })?;
+        Ok(Response::new()
+            .add_events(window_event)
+            .add_events(fee_event)
+            .add_attribute("action", "deposit")
+            .add_attribute("sender", info.sender.clone())
+            .add_attribute("amount", amount)
+            .add_event(ContractEvent::Deposited {
+                chain_id,
+                sender: info.sender,
+                denom,
+                amount,
+            }))
+    }
+
+    /// Keeps the Paloma-side fee from `amount` for the current collector and
+    /// returns the rest, with the event of the charge if a fee was kept.
+    fn charge_fee(
+        storage: &mut dyn Storage,
+        denom: &str,
+        amount: Uint128,
+    ) -> StdResult<(Uint128, Option<ContractEvent>)> {
+        let Some(paloma_fee) = PALOMA_FEE.may_load(storage)? else {
+            return Ok((amount, None));
+        };
+        let fee = paloma_fee.fee(amount);
+        let collector = paloma_fee.collector;
+        if fee.is_zero() {
+            return Ok((amount, None));
+        }
+        ACCRUED_FEES.update(
+            storage,
+            (&collector, denom.to_string()),
+            |accrued| -> StdResult<_> { Ok(accrued.unwrap_or_default() + fee) },
+        )?;
+        let event = ContractEvent::FeeCharged {
+            collector,
+            denom: denom.to_string(),
+            amount: fee,
+        };
+        Ok((amount - fee, Some(event)))
+    }
+
+    /// Takes up to `amount` out of the deposits that already paid the fee and
+    /// returns how much was covered by them.
+    fn use_fee_paid_deposits(
+        storage: &mut dyn Storage,
+        denom: &str,
+        amount: Uint128,
+    ) -> StdResult<Uint128> {
+        let deposits = FEE_PAID_DEPOSITS
+            .may_load(storage, denom.to_string())?
+            .unwrap_or_default();
+        let covered = deposits.min(amount);
+        if !covered.is_zero() {
+            FEE_PAID_DEPOSITS.save(storage, denom.to_string(), &(deposits - covered))?;
+        }
+        Ok(covered)
+    }
+
+    /// Total of the Paloma-side fees of a denom not yet claimed by any collector.
+    pub fn accrued_fees(storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+        let mut total = Uint128::zero();
+        for item in ACCRUED_FEES.range(storage, None, None, Order::Ascending) {
+            let ((_, fee_denom), amount) = item?;
+            if fee_denom == denom {
+                total += amount;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Refuses to send `amount` of a denom out of the contract when it would
+    /// spend the balance backing fees not yet claimed.
+    fn check_unclaimed_fees(
+        deps: Deps,
+        env: &Env,
+        denom: &str,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        let fees = accrued_fees(deps.storage, denom)?;
+        if fees.is_zero() {
+            return Ok(());
+        }
+        let balance = deps
+            .querier
+            .query_balance(env.contract.address.clone(), denom)?
+            .amount;
+        let available = balance.saturating_sub(fees);
+        if amount > available {
+            return Err(ContractError::FeesUnclaimed { available });
+        }
+        Ok(())
+    }
+
+    pub fn set_paloma_fee(
         deps: DepsMut,
         info: MessageInfo,
-        transaction_id: u64,
+        bps: u16,
+        collector: String,
     ) -> Result<Response<PalomaMsg>, ContractError> {
         let state = STATE.load(deps.storage)?;
-        assert!(info.sender == state.owner, "Unauthorized");
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if bps > MAX_PALOMA_FEE_BPS {
+            return Err(ContractError::InvalidFeeBps {
+                max: MAX_PALOMA_FEE_BPS,
+            });
+        }
+        let collector = deps.api.addr_validate(&collector)?;
+        PALOMA_FEE.save(
+            deps.storage,
+            &PalomaFee {
+                bps,
+                collector: collector.clone(),
+            },
+        )?;
         Ok(Response::new()
-            .add_message(CosmosMsg::Custom(PalomaMsg::SkywayMsg {
-                send_tx: None,
-                cancel_tx: Some(CancelTx { transaction_id }),
+            .add_attribute("action", "set_paloma_fee")
+            .add_event(ContractEvent::PalomaFeeSet { bps, collector }))
+    }
+
+    /// Sends the sender all fees accrued for it, including fees accrued while
+    /// it was the collector of an earlier fee setting.
+    pub fn claim_fees(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let accrued = ACCRUED_FEES
+            .prefix(&info.sender)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        if accrued.is_empty() {
+            return Err(ContractError::NoFeesToClaim {});
+        }
+        let mut amounts = Vec::with_capacity(accrued.len());
+        for (denom, amount) in accrued {
+            ACCRUED_FEES.remove(deps.storage, (&info.sender, denom.clone()));
+            amounts.push(Coin { denom, amount });
+        }
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: amounts.clone(),
+            })
+            .add_attribute("action", "claim_fees")
+            .add_event(ContractEvent::FeesClaimed {
+                collector: info.sender,
+                amounts,
             }))
-            .add_attribute("action", "cancel_tx"))
     }
 
-    pub fn set_paloma(
+    pub fn set_supply_allowance(
         deps: DepsMut,
         info: MessageInfo,
-        chain_id: String,
+        token: Option<String>,
+        allowance: Option<Uint256>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
-        // ACTION: Implement SetPaloma
         let state = STATE.load(deps.storage)?;
-        assert!(info.sender == state.owner, "Unauthorized");
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let denom = token_denom(deps.storage, token.as_deref())?;
+        let response = Response::new()
+            .add_attribute("action", "set_supply_allowance")
+            .add_attribute("denom", denom.clone())
+            .add_event(ContractEvent::SupplyAllowanceSet {
+                denom: denom.clone(),
+                allowance,
+            });
+        match allowance {
+            Some(allowance) => {
+                SUPPLY_ALLOWANCE.save(deps.storage, denom, &allowance)?;
+                Ok(response.add_attribute("allowance", allowance))
+            }
+            None => {
+                SUPPLY_ALLOWANCE.remove(deps.storage, denom);
+                Ok(response.add_attribute("allowance", "none"))
+            }
+        }
+    }
 
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "set_paloma".to_string(),
-                vec![Function {
-                    name: "set_paloma".to_string(),
-                    inputs: vec![],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
-        };
+    pub fn add_to_denylist(
+        deps: DepsMut,
+        info: MessageInfo,
+        evm_addresses: Vec<String>,
+        paloma_addresses: Vec<String>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut denied_evm_addresses = Vec::with_capacity(evm_addresses.len());
+        for address in evm_addresses.iter() {
+            let (_, canonical_address) = parse_evm_address(address)?;
+            DENIED_EVM_ADDRESSES.save(deps.storage, canonical_address.clone(), &Empty {})?;
+            denied_evm_addresses.push(canonical_address);
+        }
+        let mut denied_paloma_addresses = Vec::with_capacity(paloma_addresses.len());
+        for address in paloma_addresses.iter() {
+            let address = deps.api.addr_validate(address)?;
+            DENIED_PALOMA_ADDRESSES.save(deps.storage, &address, &Empty {})?;
+            denied_paloma_addresses.push(address.to_string());
+        }
         Ok(Response::new()
-            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("set_paloma")
-                            .unwrap()
-                            .encode_input(&[])
-                            .unwrap(),
-                    ),
-                },
+            .add_attribute("action", "add_to_denylist")
+            .add_attribute("evm_addresses", evm_addresses.len().to_string())
+            .add_attribute("paloma_addresses", paloma_addresses.len().to_string())
+            .add_event(ContractEvent::DenylistUpdated {
+                denied: true,
+                evm_addresses: denied_evm_addresses,
+                paloma_addresses: denied_paloma_addresses,
             }))
-            .add_attribute("action", "set_paloma"))
     }
 
-    pub fn update_refund_wallet(
+    pub fn remove_from_denylist(
         deps: DepsMut,
         info: MessageInfo,
-        chain_id: String,
-        new_refund_wallet: String,
+        evm_addresses: Vec<String>,
+        paloma_addresses: Vec<String>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
         let state = STATE.load(deps.storage)?;
-        assert!(state.owner == info.sender, "Unauthorized");
-        let new_refund_wallet_address: Address =
-            Address::from_str(new_refund_wallet.as_str()).unwrap();
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "update_refund_wallet".to_string(),
-                vec![Function {
-                    name: "update_refund_wallet".to_string(),
-                    inputs: vec![Param {
-                        name: "new_refund_wallet".to_string(),
-                        kind: ParamType::Address,
-                        internal_type: None,
-                    }],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
-        };
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let mut denied_evm_addresses = Vec::with_capacity(evm_addresses.len());
+        for address in evm_addresses.iter() {
+            let (_, canonical_address) = parse_evm_address(address)?;
+            DENIED_EVM_ADDRESSES.remove(deps.storage, canonical_address.clone());
+            denied_evm_addresses.push(canonical_address);
+        }
+        let mut denied_paloma_addresses = Vec::with_capacity(paloma_addresses.len());
+        for address in paloma_addresses.iter() {
+            let address = deps.api.addr_validate(address)?;
+            DENIED_PALOMA_ADDRESSES.remove(deps.storage, &address);
+            denied_paloma_addresses.push(address.to_string());
+        }
         Ok(Response::new()
-            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("update_refund_wallet")
-                            .unwrap()
-                            .encode_input(&[Token::Address(new_refund_wallet_address)])
-                            .unwrap(),
-                    ),
-                },
+            .add_attribute("action", "remove_from_denylist")
+            .add_attribute("evm_addresses", evm_addresses.len().to_string())
+            .add_attribute("paloma_addresses", paloma_addresses.len().to_string())
+            .add_event(ContractEvent::DenylistUpdated {
+                denied: false,
+                evm_addresses: denied_evm_addresses,
+                paloma_addresses: denied_paloma_addresses,
             }))
-            .add_attribute("action", "update_refund_wallet"))
     }
 
-    pub fn update_gas_fee(
+    pub fn add_hook(
         deps: DepsMut,
         info: MessageInfo,
-        chain_id: String,
-        new_gas_fee: Uint256,
+        event: HookEvent,
+        contract: String,
     ) -> Result<Response<PalomaMsg>, ContractError> {
-        // ACTION: Implement UpdateGasFee
         let state = STATE.load(deps.storage)?;
-        assert!(info.sender == state.owner, "Unauthorized");
-        let new_gas_fee: Uint = Uint::from_big_endian(&new_gas_fee.to_be_bytes());
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "update_gas_fee".to_string(),
-                vec![Function {
-                    name: "update_gas_fee".to_string(),
-                    inputs: vec![Param {
-                        name: "new_gas_fee".to_string(),
-                        kind: ParamType::Uint(256),
-                        internal_type: None,
-                    }],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
-        };
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let contract = deps.api.addr_validate(&contract)?;
+        if HOOKS.has(deps.storage, (event, &contract)) {
+            return Err(ContractError::HookExists {});
+        }
+        let subscribers = HOOKS
+            .prefix(event)
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .count();
+        if subscribers >= MAX_HOOKS as usize {
+            return Err(ContractError::TooManyHooks { max: MAX_HOOKS });
+        }
+        HOOKS.save(deps.storage, (event, &contract), &Empty {})?;
         Ok(Response::new()
-            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("update_gas_fee")
-                            .unwrap()
-                            .encode_input(&[Token::Uint(new_gas_fee)])
-                            .unwrap(),
-                    ),
-                },
-            }))
-            .add_attribute("action", "update_gas_fee"))
+            .add_attribute("action", "add_hook")
+            .add_attribute("event", event.as_str())
+            .add_attribute("contract", contract.clone())
+            .add_event(ContractEvent::HookAdded { event, contract }))
     }
 
-    pub fn update_service_fee_collector(
+    pub fn remove_hook(
+        deps: DepsMut,
+        info: MessageInfo,
+        event: HookEvent,
+        contract: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let contract = deps.api.addr_validate(&contract)?;
+        if !HOOKS.has(deps.storage, (event, &contract)) {
+            return Err(ContractError::UnknownHook {});
+        }
+        HOOKS.remove(deps.storage, (event, &contract));
+        Ok(Response::new()
+            .add_attribute("action", "remove_hook")
+            .add_attribute("event", event.as_str())
+            .add_attribute("contract", contract.clone())
+            .add_event(ContractEvent::HookRemoved { event, contract }))
+    }
+
+    pub fn set_conversion_ratio(
         deps: DepsMut,
         info: MessageInfo,
         chain_id: String,
-        new_service_fee_collector: String,
+        epoch: u64,
+        conversion_ratio: ConversionRatio,
     ) -> Result<Response<PalomaMsg>, ContractError> {
-        // ACTION: Implement UpdateServiceFeeCollector
         let state = STATE.load(deps.storage)?;
-        assert!(info.sender == state.owner, "Unauthorized");
-        let new_service_fee_collector: Address =
-            Address::from_str(new_service_fee_collector.as_str()).unwrap();
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "update_service_fee_collector".to_string(),
-                vec![Function {
-                    name: "update_service_fee_collector".to_string(),
-                    inputs: vec![Param {
-                        name: "new_service_fee_collector".to_string(),
-                        kind: ParamType::Address,
-                        internal_type: None,
-                    }],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
-        };
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if conversion_ratio.ratio.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        // Past epochs are kept as history and can no longer be changed.
+        if let Some(current_epoch) = CURRENT_EPOCH.may_load(deps.storage, chain_id.clone())? {
+            if epoch < current_epoch {
+                return Err(ContractError::StaleEpoch {
+                    epoch,
+                    current_epoch,
+                });
+            }
+        }
+        CONVERSION_RATIOS.save(deps.storage, (chain_id.clone(), epoch), &conversion_ratio)?;
+        CURRENT_EPOCH.save(deps.storage, chain_id.clone(), &epoch)?;
         Ok(Response::new()
-            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("update_service_fee_collector")
-                            .unwrap()
-                            .encode_input(&[Token::Address(new_service_fee_collector)])
-                            .unwrap(),
-                    ),
-                },
+            .add_attribute("action", "set_conversion_ratio")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("ratio", conversion_ratio.ratio.to_string())
+            .add_event(ContractEvent::ConversionRatioSet {
+                chain_id,
+                epoch,
+                ratio: conversion_ratio.ratio,
+                palomagold_decimals: conversion_ratio.palomagold_decimals,
+                evm_decimals: conversion_ratio.evm_decimals,
             }))
-            .add_attribute("action", "update_service_fee_collector"))
     }
 
-    pub fn update_service_fee(
-        deps: DepsMut,
+    #[allow(clippy::too_many_arguments)]
+    pub fn release_from_aave(
+        mut deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         chain_id: String,
-        new_service_fee: Uint256,
+        recipient: String,
+        aave_amount: Uint256,
+        nonce: Uint256,
+        epoch: Option<u64>,
     ) -> Result<Response<PalomaMsg>, ContractError> {
-        // ACTION: Implement UpdateServiceFee
         let state = STATE.load(deps.storage)?;
-        assert!(info.sender == state.owner, "Unauthorized");
-        let new_service_fee: Uint = Uint::from_big_endian(&new_service_fee.to_be_bytes());
-        #[allow(deprecated)]
-        let contract: Contract = Contract {
-            constructor: None,
-            functions: BTreeMap::from_iter(vec![(
-                "update_service_fee".to_string(),
-                vec![Function {
-                    name: "update_service_fee".to_string(),
-                    inputs: vec![Param {
-                        name: "new_service_fee".to_string(),
-                        kind: ParamType::Uint(256),
-                        internal_type: None,
-                    }],
-                    outputs: Vec::new(),
-                    constant: None,
-                    state_mutability: StateMutability::NonPayable,
-                }],
-            )]),
-            events: BTreeMap::new(),
-            errors: BTreeMap::new(),
-            receive: false,
-            fallback: false,
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let epoch = match epoch {
+            Some(epoch) => epoch,
+            None => CURRENT_EPOCH
+                .may_load(deps.storage, chain_id.clone())?
+                .ok_or(ContractError::NoConversionRatio {})?,
         };
+        let conversion_ratio = CONVERSION_RATIOS
+            .may_load(deps.storage, (chain_id.clone(), epoch))?
+            .ok_or(ContractError::NoConversionRatio {})?;
+        let release_amount = convert_amount(&conversion_ratio, aave_amount)?;
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, true)?;
+
+        let prepared = prepare_release(
+            deps.branch(),
+            &env,
+            &chain_id,
+            &recipient,
+            release_amount,
+            nonce,
+            None,
+            false,
+        )?;
+
+        let event = prepared.scheduled_event();
         Ok(Response::new()
             .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
-                execute_job: ExecuteJob {
-                    job_id: CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?.job_id,
-                    payload: Binary::new(
-                        contract
-                            .function("update_service_fee")
-                            .unwrap()
-                            .encode_input(&[Token::Uint(new_service_fee)])
-                            .unwrap(),
-                    ),
-                },
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: prepared.job_id.clone(),
+                    payload: release_payload(&prepared),
+                }),
             }))
-            .add_attribute("action", "update_service_fee"))
+            .add_events(window_event)
+            .add_submessages(hook_messages(deps.storage, &event)?)
+            .add_event(event)
+            .add_attribute("action", "release_from_aave")
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("aave_amount", aave_amount)
+            .add_attribute("release_amount", release_amount))
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::PalomagoldBalance {} => query::palomagold_balance(deps, env),
+    /// Converts a source AAVE amount into the release amount of the EVM token.
+    ///
+    /// The result is `aave_amount * ratio * 10^evm_decimals / 10^palomagold_decimals`,
+    /// computed with a single rounding step that always rounds down.
+    pub fn convert_amount(
+        conversion_ratio: &ConversionRatio,
+        aave_amount: Uint256,
+    ) -> Result<Uint256, ContractError> {
+        let ten = Uint256::from(10u8);
+        let scaled = ten
+            .checked_pow(conversion_ratio.evm_decimals.into())
+            .and_then(|scale| aave_amount.checked_mul(scale))
+            .map_err(|_| ContractError::ConversionOverflow {})?;
+        let converted = scaled
+            .checked_mul_floor(conversion_ratio.ratio)
+            .map_err(|_| ContractError::ConversionOverflow {})?;
+        let amount = ten
+            .checked_pow(conversion_ratio.palomagold_decimals.into())
+            .map(|scale| converted / scale)
+            .map_err(|_| ContractError::ConversionOverflow {})?;
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        Ok(amount)
     }
-}
 
-pub mod query {
-    use cosmwasm_std::to_json_binary;
+    pub fn batch_release(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
+        releases: Vec<ReleaseItem>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if releases.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, true)?;
 
-    use super::*;
-    use crate::msg::BalanceResponse;
+        // Every entry is validated and its nonce marked before any message is
+        // built, so a single bad entry reverts the whole batch.
+        let mut prepared: Vec<PreparedRelease> = Vec::with_capacity(releases.len());
+        for item in releases {
+            prepared.push(prepare_release(
+                deps.branch(),
+                &env,
+                &chain_id,
+                &item.recipient,
+                item.amount,
+                item.nonce,
+                None,
+                false,
+            )?);
+        }
+        let count = prepared.len();
+        let scheduled: Vec<ContractEvent> = prepared
+            .iter()
+            .map(PreparedRelease::scheduled_event)
+            .collect();
+        // Subscribers get the whole batch in one delivery rather than one per entry.
+        let hooks = subscriber_messages(
+            deps.storage,
+            HookEvent::ReleaseScheduled,
+            &HookMsg::MigratorHookBatch {
+                events: scheduled.clone(),
+            },
+        )?;
+        let events: Vec<Event> = scheduled.into_iter().map(Event::from).collect();
 
-    pub fn palomagold_balance(deps: Deps, env: Env) -> StdResult<Binary> {
-        to_json_binary(&BalanceResponse {
-            balance: deps
-                .querier
-                .query_balance(
-                    env.contract.address,
-                    STATE.load(deps.storage)?.palomagold_denom,
-                )?
-                .amount,
-        })
+        let messages: Vec<CosmosMsg<PalomaMsg>> = if chain_setting.batch_release {
+            #[allow(deprecated)]
+            let contract: Contract = Contract {
+                constructor: None,
+                functions: BTreeMap::from_iter(vec![(
+                    "release_batch".to_string(),
+                    vec![Function {
+                        name: "release_batch".to_string(),
+                        inputs: vec![
+                            Param {
+                                name: "recipients".to_string(),
+                                kind: ParamType::Array(Box::new(ParamType::Address)),
+                                internal_type: None,
+                            },
+                            Param {
+                                name: "amounts".to_string(),
+                                kind: ParamType::Array(Box::new(ParamType::Uint(256))),
+                                internal_type: None,
+                            },
+                            Param {
+                                name: "nonces".to_string(),
+                                kind: ParamType::Array(Box::new(ParamType::Uint(256))),
+                                internal_type: None,
+                            },
+                        ],
+                        outputs: Vec::new(),
+                        constant: None,
+                        state_mutability: StateMutability::NonPayable,
+                    }],
+                )]),
+                events: BTreeMap::new(),
+                errors: BTreeMap::new(),
+                receive: false,
+                fallback: false,
+            };
+            // During a job rotation, retries on the old job are batched separately.
+            let mut batches: Vec<(&str, Vec<&PreparedRelease>)> = vec![];
+            for release in &prepared {
+                match batches
+                    .iter_mut()
+                    .find(|(job_id, _)| *job_id == release.job_id)
+                {
+                    Some((_, batch)) => batch.push(release),
+                    None => batches.push((&release.job_id, vec![release])),
+                }
+            }
+            batches
+                .into_iter()
+                .map(|(job_id, batch)| {
+                    let mut recipients: Vec<Token> = Vec::with_capacity(batch.len());
+                    let mut amounts: Vec<Token> = Vec::with_capacity(batch.len());
+                    let mut nonces: Vec<Token> = Vec::with_capacity(batch.len());
+                    for release in batch {
+                        recipients.push(Token::Address(release.recipient_address));
+                        amounts.push(Token::Uint(release.abi_amount()));
+                        nonces.push(Token::Uint(release.abi_nonce()));
+                    }
+                    CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                        create_job: None,
+                        execute_job: Some(ExecuteJob {
+                            job_id: job_id.to_string(),
+                            payload: Binary::new(
+                                contract
+                                    .function("release_batch")
+                                    .unwrap()
+                                    .encode_input(&[
+                                        Token::Array(recipients),
+                                        Token::Array(amounts),
+                                        Token::Array(nonces),
+                                    ])
+                                    .unwrap(),
+                            ),
+                        }),
+                    })
+                })
+                .collect()
+        } else {
+            prepared
+                .into_iter()
+                .map(|release| {
+                    CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                        create_job: None,
+                        execute_job: Some(ExecuteJob {
+                            job_id: release.job_id.clone(),
+                            payload: release_payload(&release),
+                        }),
+                    })
+                })
+                .collect()
+        };
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_events(window_event)
+            .add_submessages(hooks)
+            .add_events(events)
+            .add_attribute("action", "batch_release")
+            .add_attribute("chain_id", chain_id)
+            .add_attribute("count", count.to_string()))
     }
-}
 
-#[cfg(test)]
-mod tests {}
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_vesting(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        recipient: String,
+        start: Timestamp,
+        cliff: u64,
+        duration: u64,
+        total: Uint256,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if duration == 0 || cliff > duration || total.is_zero() {
+            return Err(ContractError::InvalidVesting {});
+        }
+        let (_, canonical_recipient) = parse_evm_address(&recipient)?;
+        CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        let key = (chain_id.clone(), canonical_recipient.clone());
+        if VESTING_SCHEDULES.has(deps.storage, key.clone()) {
+            return Err(ContractError::VestingExists {});
+        }
+        VESTING_SCHEDULES.save(
+            deps.storage,
+            key,
+            &VestingSchedule {
+                start,
+                cliff,
+                duration,
+                total,
+                released: Uint256::zero(),
+            },
+        )?;
+        Ok(Response::new()
+            .add_attribute("action", "create_vesting")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_attribute("recipient", recipient)
+            .add_attribute("total", total)
+            .add_event(ContractEvent::VestingCreated {
+                chain_id,
+                recipient: canonical_recipient,
+                start,
+                cliff,
+                duration,
+                total,
+            }))
+    }
+
+    pub fn release_vested(
+        mut deps: DepsMut,
+        env: Env,
+        chain_id: String,
+        recipient: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let key = (chain_id.clone(), parse_evm_address(&recipient)?.1);
+        let mut schedule = VESTING_SCHEDULES.load(deps.storage, key.clone())?;
+        let releasable = schedule.vested(env.block.time) - schedule.released;
+        if releasable.is_zero() {
+            return Err(ContractError::NothingToRelease {});
+        }
+        schedule.released += releasable;
+        VESTING_SCHEDULES.save(deps.storage, key, &schedule)?;
+
+        let window_event = check_migration_window(deps.branch(), &env, &chain_id, true)?;
+        let nonce = next_contract_nonce(deps.storage, &chain_id)?;
+        let prepared = prepare_release(
+            deps.branch(),
+            &env,
+            &chain_id,
+            &recipient,
+            releasable,
+            nonce,
+            None,
+            true,
+        )?;
+
+        let event = prepared.scheduled_event();
+        Ok(Response::new()
+            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: prepared.job_id.clone(),
+                    payload: release_payload(&prepared),
+                }),
+            }))
+            .add_events(window_event)
+            .add_submessages(hook_messages(deps.storage, &event)?)
+            .add_event(event)
+            .add_attribute("action", "release_vested")
+            .add_attribute("chain_id", chain_id)
+            .add_attribute("recipient", recipient)
+            .add_attribute("amount", releasable)
+            .add_attribute("nonce", nonce))
+    }
+
+    /// Returns a fresh release nonce for releases initiated by the contract.
+    /// These nonces have the highest bit set and are refused on every other
+    /// release path, so they never collide with the nonces chosen by the owner.
+    fn next_contract_nonce(
+        storage: &mut dyn Storage,
+        chain_id: &str,
+    ) -> Result<Uint256, ContractError> {
+        let counter = CONTRACT_NONCE
+            .may_load(storage, chain_id.to_string())?
+            .unwrap_or_default();
+        CONTRACT_NONCE.save(storage, chain_id.to_string(), &(counter + 1))?;
+        Ok(contract_nonce_base() + Uint256::from(counter))
+    }
+
+    /// Takes a release amount back out of a total. Totals seeded by imports
+    /// or backfills may not include the release, which is refused.
+    fn subtract(total: Uint256, amount: Uint256, name: &str) -> Result<Uint256, ContractError> {
+        total
+            .checked_sub(amount)
+            .map_err(|_| ContractError::TotalsUnderflow {
+                total: name.to_string(),
+            })
+    }
+
+    fn contract_nonce_base() -> Uint256 {
+        Uint256::one() << 255
+    }
+
+    /// Refuses nonces reserved for the releases of vesting schedules.
+    fn check_owner_nonce(nonce: Uint256) -> Result<(), ContractError> {
+        if nonce >= contract_nonce_base() {
+            return Err(ContractError::ReservedNonce { nonce });
+        }
+        Ok(())
+    }
+
+    pub fn cancel_release(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        nonce: Uint256,
+        forward: bool,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let release_key = (chain_id.clone(), nonce.to_string());
+        let mut record = releases()
+            .may_load(deps.storage, release_key.clone())?
+            .ok_or(ContractError::UnknownRelease {})?;
+        check_scheduled(&record)?;
+        record.status = ReleaseStatus::Abandoned;
+        releases().save(deps.storage, release_key, &record)?;
+
+        // Give the capacity used by the release back to the recipient and the supply.
+        let denom = token_denom(deps.storage, record.token.as_deref())?;
+        let totals_key = (chain_id.clone(), record.recipient.clone(), denom.clone());
+        let mut totals = RECIPIENT_TOTALS.load(deps.storage, totals_key.clone())?;
+        totals.released = subtract(totals.released, record.amount, "recipient totals")?;
+        RECIPIENT_TOTALS.save(deps.storage, totals_key, &totals)?;
+        let denom_amount = record.denom_amount.unwrap_or(record.amount);
+        let mut supply = SUPPLY_TOTALS.load(deps.storage, denom.clone())?;
+        supply.released = subtract(supply.released, denom_amount, "supply totals")?;
+        SUPPLY_TOTALS.save(deps.storage, denom.clone(), &supply)?;
+        RELEASED_PER_CHAIN.update(
+            deps.storage,
+            (denom.clone(), chain_id.clone()),
+            |released| subtract(released.unwrap_or_default(), denom_amount, "chain total"),
+        )?;
+        RESERVED_RELEASES.update(deps.storage, denom, |reserved| -> StdResult<_> {
+            Ok(reserved.unwrap_or_default().saturating_sub(denom_amount))
+        })?;
+        // Vested amounts that were not released can be released again.
+        if record.vested {
+            let vesting_key = (chain_id.clone(), record.recipient.clone());
+            if let Some(mut schedule) =
+                VESTING_SCHEDULES.may_load(deps.storage, vesting_key.clone())?
+            {
+                schedule.released = subtract(schedule.released, record.amount, "vesting schedule")?;
+                VESTING_SCHEDULES.save(deps.storage, vesting_key, &schedule)?;
+            }
+        }
+
+        let event = ContractEvent::ReleaseCancelled {
+            chain_id: chain_id.clone(),
+            nonce,
+            recipient: record.recipient,
+            amount: record.amount,
+            forwarded: forward,
+        };
+        let mut response = Response::new()
+            .add_attribute("action", "cancel_release")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_attribute("nonce", nonce)
+            .add_attribute("amount", record.amount)
+            .add_submessages(hook_messages(deps.storage, &event)?)
+            .add_event(event);
+        if forward {
+            let nonce: Uint = Uint::from_big_endian(&nonce.to_be_bytes());
+            #[allow(deprecated)]
+            let contract: Contract = Contract {
+                constructor: None,
+                functions: BTreeMap::from_iter(vec![(
+                    "cancel_release".to_string(),
+                    vec![Function {
+                        name: "cancel_release".to_string(),
+                        inputs: vec![Param {
+                            name: "nonce".to_string(),
+                            kind: ParamType::Uint(256),
+                            internal_type: None,
+                        }],
+                        outputs: Vec::new(),
+                        constant: None,
+                        state_mutability: StateMutability::NonPayable,
+                    }],
+                )]),
+                events: BTreeMap::new(),
+                errors: BTreeMap::new(),
+                receive: false,
+                fallback: false,
+            };
+            // The cancel goes to the EVM contract the release was scheduled on,
+            // which a job rotation may since have replaced.
+            let job_id = match (
+                record.job_id,
+                JOB_ROTATIONS.may_load(deps.storage, chain_id.clone())?,
+            ) {
+                (Some(job_id), _) => job_id,
+                (None, Some(rotation)) => rotation.old_job_id,
+                // Releases without a recorded job predate purpose jobs and
+                // went through the default job of the chain.
+                (None, None) => CHAIN_SETTINGS.load(deps.storage, chain_id)?.job_id,
+            };
+            response = response.add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id,
+                    payload: Binary::new(
+                        contract
+                            .function("cancel_release")
+                            .unwrap()
+                            .encode_input(&[Token::Uint(nonce)])
+                            .unwrap(),
+                    ),
+                }),
+            }));
+        }
+        Ok(response)
+    }
+
+    pub fn set_migration_window(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        chain_id: String,
+        window: Option<MigrationWindow>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let response = Response::new()
+            .add_attribute("action", "set_migration_window")
+            .add_attribute("chain_id", chain_id.clone())
+            .add_event(ContractEvent::MigrationWindowSet {
+                chain_id: chain_id.clone(),
+                start: window.as_ref().map(|window| window.start),
+                end: window.as_ref().map(|window| window.end),
+                grace_period: window.as_ref().and_then(|window| window.grace_period),
+            });
+        match window {
+            Some(window) => {
+                if window.start >= window.end {
+                    return Err(ContractError::InvalidWindow {});
+                }
+                MIGRATION_WINDOWS.save(deps.storage, chain_id.clone(), &window)?;
+                let window_event = record_window_status(deps, &env, &chain_id, &window)?;
+                Ok(response.add_events(window_event))
+            }
+            None => {
+                MIGRATION_WINDOWS.remove(deps.storage, chain_id.clone());
+                WINDOW_STATUS.remove(deps.storage, chain_id);
+                Ok(response)
+            }
+        }
+    }
+
+    pub fn sync_migration_window(
+        deps: DepsMut,
+        env: Env,
+        chain_id: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let window = MIGRATION_WINDOWS.load(deps.storage, chain_id.clone())?;
+        let window_event = record_window_status(deps, &env, &chain_id, &window)?;
+        Ok(Response::new()
+            .add_events(window_event)
+            .add_attribute("action", "sync_migration_window")
+            .add_attribute("chain_id", chain_id))
+    }
+
+    /// Rejects the call unless the migration window of the chain is open.
+    /// Releases are also accepted during the grace period after the window ends.
+    /// Chains without a window are not restricted.
+    fn check_migration_window(
+        deps: DepsMut,
+        env: &Env,
+        chain_id: &str,
+        allow_grace: bool,
+    ) -> Result<Option<Event>, ContractError> {
+        let Some(window) = MIGRATION_WINDOWS.may_load(deps.storage, chain_id.to_string())? else {
+            return Ok(None);
+        };
+        let status = window.status(env.block.time);
+        match status {
+            WindowStatus::Open => {}
+            WindowStatus::Grace if allow_grace => {}
+            _ => {
+                return Err(ContractError::WindowNotOpen {
+                    chain_id: chain_id.to_string(),
+                    status: status.as_str().to_string(),
+                })
+            }
+        }
+        record_window_status(deps, env, chain_id, &window)
+    }
+
+    /// Stores the current window status and returns an event if it changed
+    /// since the last time it was recorded.
+    fn record_window_status(
+        deps: DepsMut,
+        env: &Env,
+        chain_id: &str,
+        window: &MigrationWindow,
+    ) -> Result<Option<Event>, ContractError> {
+        let status = window.status(env.block.time);
+        if WINDOW_STATUS.may_load(deps.storage, chain_id.to_string())? == Some(status) {
+            return Ok(None);
+        }
+        WINDOW_STATUS.save(deps.storage, chain_id.to_string(), &status)?;
+        Ok(Some(
+            ContractEvent::MigrationWindow {
+                chain_id: chain_id.to_string(),
+                status: status.as_str().to_string(),
+            }
+            .into(),
+        ))
+    }
+
+    /// A validated release entry, ready to be encoded.
+    pub struct PreparedRelease {
+        pub chain_id: String,
+        /// Canonical recipient address.
+        pub recipient: String,
+        pub recipient_address: Address,
+        pub amount: Uint256,
+        pub nonce: Uint256,
+        /// Registered token key, `None` for palomagold.
+        pub token: Option<String>,
+        /// EVM address of the registered token on the chain.
+        pub token_address: Option<Address>,
+        /// Job the release is sent through.
+        pub job_id: String,
+        /// Whether the nonce was scheduled before.
+        pub retry: bool,
+    }
+
+    impl PreparedRelease {
+        fn abi_amount(&self) -> Uint {
+            Uint::from_big_endian(&self.amount.to_be_bytes())
+        }
+
+        fn abi_nonce(&self) -> Uint {
+            Uint::from_big_endian(&self.nonce.to_be_bytes())
+        }
+
+        fn scheduled_event(&self) -> ContractEvent {
+            ContractEvent::ReleaseScheduled {
+                chain_id: self.chain_id.clone(),
+                recipient: self.recipient.clone(),
+                amount: self.amount,
+                nonce: self.nonce,
+                job_id: self.job_id.clone(),
+                token: self.token.clone(),
+                retry: self.retry,
+            }
+        }
+    }
+
+    /// Validates a single release entry and marks its nonce as withdrawn.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_release(
+        deps: DepsMut,
+        env: &Env,
+        chain_id: &str,
+        recipient: &str,
+        amount: Uint256,
+        nonce: Uint256,
+        token: Option<&str>,
+        vested: bool,
+    ) -> Result<PreparedRelease, ContractError> {
+        if !vested {
+            check_owner_nonce(nonce)?;
+        }
+        if let Some(timestamp) =
+            WITHDRAW_TIMESTAMP.may_load(deps.storage, (chain_id.to_string(), nonce.to_string()))?
+        {
+            if timestamp.plus_seconds(60).gt(&env.block.time) {
+                // If the timestamp is not older than 60 seconds, return an error
+                return Err(ContractError::Pending {});
+            }
+        }
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+
+        let (recipient_address, recipient) = parse_evm_address(recipient)?;
+        check_not_denied(deps.storage, &recipient)?;
+        let token_address = match token {
+            Some(token_key) => {
+                Some(parse_evm_address(&evm_token(deps.storage, token_key, chain_id)?.address)?.0)
+            }
+            None => None,
+        };
+
+        let chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.to_string())?;
+        let rotation = JOB_ROTATIONS.may_load(deps.storage, chain_id.to_string())?;
+        let current_job_id = match &rotation {
+            Some(rotation) => rotation.new_job_id.clone(),
+            None => chain_setting.job(JobPurpose::Release).to_string(),
+        };
+        let release_key = (chain_id.to_string(), nonce.to_string());
+        let existing = load_release(deps.storage, release_key.clone())?;
+        let retry = existing.is_some();
+        let mut job_id = current_job_id.clone();
+        if let Some(record) = existing {
+            // Retries stay on the job being rotated out until the rotation is
+            // finalized. Releases that predate job records were scheduled before it.
+            if let Some(rotation) = &rotation {
+                if record
+                    .job_id
+                    .as_deref()
+                    .is_none_or(|id| id == rotation.old_job_id)
+                {
+                    job_id = rotation.old_job_id.clone();
+                }
+            }
+            if record.status == ReleaseStatus::Legacy && record.recipient.is_empty() {
+                // Nothing about the release was recorded before 0.2, so it cannot be
+                // checked against the caps until `BackfillReleases` fills it in.
+                return Err(ContractError::LegacyRelease {});
+            } else if record.recipient != recipient
+                || record.amount != amount
+                || record.token.as_deref() != token
+            {
+                // A retry must release exactly what was scheduled and is not counted again.
+                return Err(ContractError::NonceMismatch {});
+            } else if record.status != ReleaseStatus::Legacy {
+                check_scheduled(&record)?;
+                if let Some(expires_at) = record.expires_at {
+                    if env.block.time >= expires_at {
+                        return Err(ContractError::ReleaseExpired {});
+                    }
+                }
+            }
+        } else {
+            let denom = token_denom(deps.storage, token)?;
+            let totals_key = (chain_id.to_string(), recipient.clone(), denom.clone());
+            let mut totals = RECIPIENT_TOTALS
+                .may_load(deps.storage, totals_key.clone())?
+                .unwrap_or_default();
+            totals.released += amount;
+            // The recipient cap of a chain is expressed in palomagold.
+            if let (Some(cap), None) = (chain_setting.recipient_cap, token) {
+                if totals.released > cap {
+                    return Err(ContractError::RecipientCapExceeded { cap });
+                }
+            }
+            RECIPIENT_TOTALS.save(deps.storage, totals_key, &totals)?;
+
+            // Supply totals are kept in denom units, which bridged amounts are counted in.
+            let denom_amount = to_denom_units(deps.storage, chain_id, token, amount)?;
+            let mut supply = SUPPLY_TOTALS
+                .may_load(deps.storage, denom.clone())?
+                .unwrap_or_default();
+            supply.released += denom_amount;
+            if let Some(allowance) = SUPPLY_ALLOWANCE.may_load(deps.storage, denom.clone())? {
+                let limit = Uint256::from(supply.bridged) + allowance;
+                if supply.released > limit {
+                    return Err(ContractError::SupplyExceeded { limit });
+                }
+            }
+            SUPPLY_TOTALS.save(deps.storage, denom.clone(), &supply)?;
+            RELEASED_PER_CHAIN.update(
+                deps.storage,
+                (denom.clone(), chain_id.to_string()),
+                |released| -> StdResult<_> { Ok(released.unwrap_or_default() + denom_amount) },
+            )?;
+            RESERVED_RELEASES.update(deps.storage, denom, |reserved| -> StdResult<_> {
+                Ok(reserved.unwrap_or_default() + denom_amount)
+            })?;
+
+            releases().save(
+                deps.storage,
+                release_key,
+                &ReleaseRecord {
+                    chain_id: chain_id.to_string(),
+                    recipient: recipient.clone(),
+                    amount,
+                    scheduled_at: env.block.time,
+                    expires_at: chain_setting
+                        .release_ttl
+                        .map(|ttl| env.block.time.plus_seconds(ttl)),
+                    status: ReleaseStatus::Scheduled,
+                    token: token.map(str::to_string),
+                    job_id: Some(current_job_id),
+                    denom_amount: Some(denom_amount),
+                    vested,
+                },
+            )?;
+        }
+
+        WITHDRAW_TIMESTAMP.save(
+            deps.storage,
+            (chain_id.to_string(), nonce.to_string()),
+            &env.block.time,
+        )?;
+
+        Ok(PreparedRelease {
+            chain_id: chain_id.to_string(),
+            recipient,
+            recipient_address,
+            amount,
+            nonce,
+            token: token.map(str::to_string),
+            token_address,
+            job_id,
+            retry,
+        })
+    }
+
+    /// Converts an amount released on a chain from EVM base units into base
+    /// units of its Cosmos denom, rounding up so a release is never
+    /// undercounted. Palomagold uses the decimals of the current conversion
+    /// ratio of the chain and registered tokens the decimals they were
+    /// registered with; when these are unknown both units are taken to match.
+    pub fn to_denom_units(
+        storage: &dyn Storage,
+        chain_id: &str,
+        token: Option<&str>,
+        amount: Uint256,
+    ) -> Result<Uint256, ContractError> {
+        let decimals = match token {
+            Some(token_key) => {
+                let token = TOKENS.load(storage, token_key.to_string())?;
+                token.decimals.zip(
+                    token
+                        .chains
+                        .get(chain_id)
+                        .map(|evm_token| evm_token.decimals),
+                )
+            }
+            None => match CURRENT_EPOCH.may_load(storage, chain_id.to_string())? {
+                Some(epoch) => CONVERSION_RATIOS
+                    .may_load(storage, (chain_id.to_string(), epoch))?
+                    .map(|ratio| (ratio.palomagold_decimals, ratio.evm_decimals)),
+                None => None,
+            },
+        };
+        let Some((denom_decimals, evm_decimals)) = decimals else {
+            return Ok(amount);
+        };
+        let ten = Uint256::from(10u8);
+        if evm_decimals >= denom_decimals {
+            let scale = ten
+                .checked_pow((evm_decimals - denom_decimals).into())
+                .map_err(|_| ContractError::ConversionOverflow {})?;
+            let converted = amount / scale;
+            if (amount % scale).is_zero() {
+                Ok(converted)
+            } else {
+                Ok(converted + Uint256::one())
+            }
+        } else {
+            ten.checked_pow((denom_decimals - evm_decimals).into())
+                .and_then(|scale| amount.checked_mul(scale))
+                .map_err(|_| ContractError::ConversionOverflow {})
+        }
+    }
+
+    fn check_scheduled(record: &ReleaseRecord) -> Result<(), ContractError> {
+        match record.status {
+            ReleaseStatus::Scheduled => Ok(()),
+            ReleaseStatus::Abandoned => Err(ContractError::ReleaseAbandoned {}),
+            ReleaseStatus::Completed => Err(ContractError::ReleaseCompleted {}),
+            ReleaseStatus::Legacy => Err(ContractError::LegacyRelease {}),
+        }
+    }
+
+    /// Returns the release record of a nonce, or a legacy record when the
+    /// nonce was released before release records were kept.
+    fn load_release(
+        storage: &dyn Storage,
+        release_key: (String, String),
+    ) -> StdResult<Option<ReleaseRecord>> {
+        if let Some(record) = releases().may_load(storage, release_key.clone())? {
+            return Ok(Some(record));
+        }
+        Ok(WITHDRAW_TIMESTAMP
+            .may_load(storage, release_key.clone())?
+            .map(|timestamp| ReleaseRecord::legacy(release_key.0, timestamp)))
+    }
+
+    /// Records the recipient and amount of a legacy release in the release
+    /// record and the recipient totals. Supply totals are not touched, as
+    /// legacy releases predate them.
+    fn backfill_release(
+        storage: &mut dyn Storage,
+        release_key: (String, String),
+        mut record: ReleaseRecord,
+        recipient: String,
+        amount: Uint256,
+    ) -> Result<(), ContractError> {
+        let denom = token_denom(storage, None)?;
+        // An earlier backfill is moved out of the totals of its recipient.
+        if !record.recipient.is_empty() {
+            let totals_key = (
+                record.chain_id.clone(),
+                record.recipient.clone(),
+                denom.clone(),
+            );
+            let mut totals = RECIPIENT_TOTALS.load(storage, totals_key.clone())?;
+            totals.released = subtract(totals.released, record.amount, "recipient totals")?;
+            RECIPIENT_TOTALS.save(storage, totals_key, &totals)?;
+        }
+        let totals_key = (record.chain_id.clone(), recipient.clone(), denom);
+        let mut totals = RECIPIENT_TOTALS
+            .may_load(storage, totals_key.clone())?
+            .unwrap_or_default();
+        totals.released += amount;
+        RECIPIENT_TOTALS.save(storage, totals_key, &totals)?;
+        record.recipient = recipient;
+        record.amount = amount;
+        releases().save(storage, release_key, &record)?;
+        Ok(())
+    }
+
+    /// Returns the Cosmos denom of a registered token, or the palomagold denom.
+    pub fn token_denom(
+        storage: &dyn Storage,
+        token: Option<&str>,
+    ) -> Result<String, ContractError> {
+        match token {
+            Some(token_key) => Ok(TOKENS
+                .may_load(storage, token_key.to_string())?
+                .ok_or_else(|| ContractError::UnknownToken {
+                    token: token_key.to_string(),
+                })?
+                .denom),
+            None => Ok(STATE.load(storage)?.palomagold_denom),
+        }
+    }
+
+    pub fn evm_token(
+        storage: &dyn Storage,
+        token_key: &str,
+        chain_id: &str,
+    ) -> Result<EvmToken, ContractError> {
+        TOKENS
+            .may_load(storage, token_key.to_string())?
+            .and_then(|token| token.chains.get(chain_id).cloned())
+            .ok_or_else(|| ContractError::UnknownToken {
+                token: token_key.to_string(),
+            })
+    }
+
+    /// Parses an EVM address and returns it along with its canonical form,
+    /// lowercase with a `0x` prefix, which is used in storage keys.
+    pub fn parse_evm_address(address: &str) -> Result<(Address, String), ContractError> {
+        let parsed = Address::from_str(address).map_err(|_| ContractError::InvalidAddress {
+            address: address.to_string(),
+        })?;
+        Ok((parsed, format!("{:#x}", parsed)))
+    }
+
+    pub fn hook_failed(msg: Reply) -> Result<Response, ContractError> {
+        let Err(error) = msg.result.into_result() else {
+            return Ok(Response::new());
+        };
+        let delivery: HookDelivery = from_json(&msg.payload)?;
+        Ok(Response::new()
+            .add_attribute("action", "hook_failed")
+            .add_event(ContractEvent::HookFailed {
+                event: delivery.event,
+                contract: delivery.contract,
+                error,
+            }))
+    }
+
+    /// Delivers `event` to the contracts subscribed to its hook event. A
+    /// failing subscriber is reported by `reply` and does not revert the
+    /// message that triggered the event.
+    fn hook_messages(
+        storage: &dyn Storage,
+        event: &ContractEvent,
+    ) -> StdResult<Vec<SubMsg<PalomaMsg>>> {
+        let Some(hook_event) = event.hook_event() else {
+            return Ok(vec![]);
+        };
+        subscriber_messages(
+            storage,
+            hook_event,
+            &HookMsg::MigratorHook {
+                event: event.clone(),
+            },
+        )
+    }
+
+    /// Builds the delivery of `msg` to every subscriber of `hook_event`.
+    fn subscriber_messages(
+        storage: &dyn Storage,
+        hook_event: HookEvent,
+        msg: &HookMsg,
+    ) -> StdResult<Vec<SubMsg<PalomaMsg>>> {
+        let msg = to_json_binary(msg)?;
+        HOOKS
+            .prefix(hook_event)
+            .keys(storage, None, None, Order::Ascending)
+            .map(|contract| {
+                let contract = contract?;
+                let payload = to_json_binary(&HookDelivery {
+                    event: hook_event,
+                    contract: contract.clone(),
+                })?;
+                Ok(SubMsg::reply_on_error(
+                    WasmMsg::Execute {
+                        contract_addr: contract.into_string(),
+                        msg: msg.clone(),
+                        funds: vec![],
+                    },
+                    HOOK_REPLY_ID,
+                )
+                .with_gas_limit(HOOK_GAS_LIMIT)
+                .with_payload(payload))
+            })
+            .collect()
+    }
+
+    fn check_not_denied(
+        storage: &dyn Storage,
+        canonical_address: &str,
+    ) -> Result<(), ContractError> {
+        if DENIED_EVM_ADDRESSES.has(storage, canonical_address.to_string()) {
+            return Err(ContractError::Denied {
+                address: canonical_address.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn release_payload(release: &PreparedRelease) -> Binary {
+        let recipient_param = Param {
+            name: "recipient".to_string(),
+            kind: ParamType::Address,
+            internal_type: None,
+        };
+        let amount_param = Param {
+            name: "amount".to_string(),
+            kind: ParamType::Uint(256),
+            internal_type: None,
+        };
+        let nonce_param = Param {
+            name: "nonce".to_string(),
+            kind: ParamType::Uint(256),
+            internal_type: None,
+        };
+        let (function_name, inputs, tokens) = match release.token_address {
+            Some(token) => (
+                "release_token",
+                vec![
+                    Param {
+                        name: "token".to_string(),
+                        kind: ParamType::Address,
+                        internal_type: None,
+                    },
+                    recipient_param,
+                    amount_param,
+                    nonce_param,
+                ],
+                vec![
+                    Token::Address(token),
+                    Token::Address(release.recipient_address),
+                    Token::Uint(release.abi_amount()),
+                    Token::Uint(release.abi_nonce()),
+                ],
+            ),
+            None => (
+                "release",
+                vec![recipient_param, amount_param, nonce_param],
+                vec![
+                    Token::Address(release.recipient_address),
+                    Token::Uint(release.abi_amount()),
+                    Token::Uint(release.abi_nonce()),
+                ],
+            ),
+        };
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                function_name.to_string(),
+                vec![Function {
+                    name: function_name.to_string(),
+                    inputs,
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        Binary::new(
+            contract
+                .function(function_name)
+                .unwrap()
+                .encode_input(&tokens)
+                .unwrap(),
+        )
+    }
+
+    pub fn cancel_tx(
+        deps: DepsMut,
+        info: MessageInfo,
+        transaction_id: u64,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        assert!(info.sender == state.owner, "Unauthorized");
+        Ok(Response::new()
+            .add_message(CosmosMsg::Custom(PalomaMsg::SkywayMsg {
+                send_tx: None,
+                cancel_tx: Some(CancelTx { transaction_id }),
+            }))
+            .add_attribute("action", "cancel_tx")
+            .add_event(ContractEvent::SkywayTxCancelled { transaction_id }))
+    }
+
+    pub fn convert_legacy_releases(
+        deps: DepsMut,
+        info: MessageInfo,
+        limit: Option<u32>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        match LEGACY_CONVERSION.may_load(deps.storage)? {
+            Some(conversion) if !conversion.done => {}
+            _ => return Err(ContractError::NoLegacyConversion {}),
+        }
+        let conversion = migration::convert_legacy_releases(
+            deps.storage,
+            limit.unwrap_or(LEGACY_CONVERSION_BATCH),
+        )?;
+        Ok(Response::new()
+            .add_attribute("action", "convert_legacy_releases")
+            .add_attribute("converted", conversion.converted.to_string())
+            .add_attribute("done", conversion.done.to_string())
+            .add_event(ContractEvent::LegacyReleasesConverted {
+                converted: conversion.converted,
+                done: conversion.done,
+            }))
+    }
+
+    pub fn backfill_releases(
+        deps: DepsMut,
+        info: MessageInfo,
+        items: Vec<BackfillItem>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if items.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let count = items.len();
+        let mut events = vec![];
+        for item in items {
+            if item.amount.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            check_owner_nonce(item.nonce)?;
+            let (_, recipient) = parse_evm_address(&item.recipient)?;
+            let release_key = (item.chain_id.clone(), item.nonce.to_string());
+            let record = load_release(deps.storage, release_key.clone())?
+                .ok_or(ContractError::UnknownRelease {})?;
+            if record.status != ReleaseStatus::Legacy {
+                return Err(ContractError::NotLegacyRelease {});
+            }
+            backfill_release(
+                deps.storage,
+                release_key,
+                record,
+                recipient.clone(),
+                item.amount,
+            )?;
+            events.push(Event::from(ContractEvent::ReleaseBackfilled {
+                chain_id: item.chain_id,
+                nonce: item.nonce,
+                recipient,
+                amount: item.amount,
+            }));
+        }
+        Ok(Response::new()
+            .add_attribute("action", "backfill_releases")
+            .add_attribute("count", count.to_string())
+            .add_events(events))
+    }
+
+    pub fn import_state(
+        deps: DepsMut,
+        info: MessageInfo,
+        records: Vec<StateRecord>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if SEALED.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::Sealed {});
+        }
+        let count = records.len();
+        for record in records {
+            if let StateRecord::Release { nonce, record } = &record {
+                if !record.vested {
+                    check_owner_nonce(Uint256::from_str(nonce)?)?;
+                }
+            }
+            import_record(deps.storage, record)?;
+        }
+        Ok(Response::new()
+            .add_attribute("action", "import_state")
+            .add_attribute("count", count.to_string())
+            .add_event(ContractEvent::StateImported {
+                count: count as u64,
+            }))
+    }
+
+    fn import_record(storage: &mut dyn Storage, record: StateRecord) -> StdResult<()> {
+        match record {
+            StateRecord::State(state) => STATE.save(storage, &state),
+            StateRecord::ChainSetting {
+                chain_id,
+                chain_setting,
+            } => CHAIN_SETTINGS.save(storage, chain_id, &chain_setting),
+            StateRecord::WithdrawTimestamp {
+                chain_id,
+                nonce,
+                timestamp,
+            } => WITHDRAW_TIMESTAMP.save(storage, (chain_id, nonce), &timestamp),
+            StateRecord::ConversionRatio {
+                chain_id,
+                epoch,
+                conversion_ratio,
+            } => CONVERSION_RATIOS.save(storage, (chain_id, epoch), &conversion_ratio),
+            StateRecord::CurrentEpoch { chain_id, epoch } => {
+                CURRENT_EPOCH.save(storage, chain_id, &epoch)
+            }
+            StateRecord::MigrationWindow { chain_id, window } => {
+                MIGRATION_WINDOWS.save(storage, chain_id, &window)
+            }
+            StateRecord::WindowStatus { chain_id, status } => {
+                WINDOW_STATUS.save(storage, chain_id, &status)
+            }
+            StateRecord::VestingSchedule {
+                chain_id,
+                recipient,
+                schedule,
+            } => VESTING_SCHEDULES.save(storage, (chain_id, recipient), &schedule),
+            StateRecord::ContractNonce { chain_id, counter } => {
+                CONTRACT_NONCE.save(storage, chain_id, &counter)
+            }
+            StateRecord::RecipientTotals {
+                chain_id,
+                recipient,
+                denom,
+                totals,
+            } => RECIPIENT_TOTALS.save(storage, (chain_id, recipient, denom), &totals),
+            StateRecord::Release { nonce, record } => {
+                releases().save(storage, (record.chain_id.clone(), nonce), &record)
+            }
+            StateRecord::SupplyTotals { denom, totals } => {
+                SUPPLY_TOTALS.save(storage, denom, &totals)
+            }
+            StateRecord::ReleasedPerChain {
+                denom,
+                chain_id,
+                released,
+            } => RELEASED_PER_CHAIN.save(storage, (denom, chain_id), &released),
+            StateRecord::SupplyAllowance { denom, allowance } => {
+                SUPPLY_ALLOWANCE.save(storage, denom, &allowance)
+            }
+            StateRecord::DeniedEvmAddress { address } => {
+                DENIED_EVM_ADDRESSES.save(storage, address, &Empty {})
+            }
+            StateRecord::DeniedPalomaAddress { address } => {
+                DENIED_PALOMA_ADDRESSES.save(storage, &address, &Empty {})
+            }
+            StateRecord::PendingEmergencyWithdrawal(withdrawal) => {
+                PENDING_EMERGENCY_WITHDRAWAL.save(storage, &withdrawal)
+            }
+            StateRecord::Token { token_key, token } => TOKENS.save(storage, token_key, &token),
+            StateRecord::ReservedReleases { denom, reserved } => {
+                RESERVED_RELEASES.save(storage, denom, &reserved)
+            }
+            StateRecord::SkywayCommitted { denom, committed } => {
+                SKYWAY_COMMITTED.save(storage, denom, &committed)
+            }
+            StateRecord::LegacyConversion(conversion) => {
+                LEGACY_CONVERSION.save(storage, &conversion)
+            }
+            StateRecord::Hook { event, contract } => {
+                HOOKS.save(storage, (event, &contract), &Empty {})
+            }
+            StateRecord::JobRotation { chain_id, rotation } => {
+                JOB_ROTATIONS.save(storage, chain_id, &rotation)
+            }
+            StateRecord::FeeBounds {
+                chain_id,
+                fee,
+                bounds,
+            } => FEE_BOUNDS.save(storage, (chain_id, fee), &bounds),
+            StateRecord::ForwardedFee {
+                chain_id,
+                fee,
+                value,
+            } => FORWARDED_FEES.save(storage, (chain_id, fee), &value),
+            StateRecord::FeeOverride {
+                chain_id,
+                fee,
+                value,
+            } => FEE_OVERRIDES.save(storage, (chain_id, fee), &value),
+            StateRecord::FeeOverrideApprover(approver) => {
+                FEE_OVERRIDE_APPROVER.save(storage, &approver)
+            }
+            StateRecord::PalomaFee(fee) => PALOMA_FEE.save(storage, &fee),
+            StateRecord::AccruedFee {
+                collector,
+                denom,
+                amount,
+            } => ACCRUED_FEES.save(storage, (&collector, denom), &amount),
+            StateRecord::FeePaidDeposit { denom, amount } => {
+                FEE_PAID_DEPOSITS.save(storage, denom, &amount)
+            }
+        }
+    }
+
+    pub fn seal(deps: DepsMut, info: MessageInfo) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if SEALED.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::Sealed {});
+        }
+        SEALED.save(deps.storage, &true)?;
+        Ok(Response::new()
+            .add_attribute("action", "seal")
+            .add_event(ContractEvent::Sealed {}))
+    }
+
+    pub fn confirm_releases(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        nonces: Vec<Uint256>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if nonces.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        let count = nonces.len();
+        let mut events = vec![];
+        for nonce in nonces {
+            let release_key = (chain_id.clone(), nonce.to_string());
+            let mut record = releases()
+                .may_load(deps.storage, release_key.clone())?
+                .ok_or(ContractError::UnknownRelease {})?;
+            check_scheduled(&record)?;
+            record.status = ReleaseStatus::Completed;
+            releases().save(deps.storage, release_key, &record)?;
+            let denom = token_denom(deps.storage, record.token.as_deref())?;
+            // Releases scheduled before reserves were tracked are not part of them.
+            let denom_amount = record.denom_amount.unwrap_or(record.amount);
+            RESERVED_RELEASES.update(deps.storage, denom, |reserved| -> StdResult<_> {
+                Ok(reserved.unwrap_or_default().saturating_sub(denom_amount))
+            })?;
+            events.push(Event::from(ContractEvent::ReleaseConfirmed {
+                chain_id: chain_id.clone(),
+                nonce,
+                recipient: record.recipient,
+                amount: record.amount,
+            }));
+        }
+        Ok(Response::new()
+            .add_attribute("action", "confirm_releases")
+            .add_attribute("chain_id", chain_id)
+            .add_attribute("count", count.to_string())
+            .add_events(events))
+    }
+
+    pub fn settle_skyway_sends(
+        deps: DepsMut,
+        info: MessageInfo,
+        token: Option<String>,
+        amount: Uint128,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let denom = token_denom(deps.storage, token.as_deref())?;
+        let committed = SKYWAY_COMMITTED
+            .may_load(deps.storage, denom.clone())?
+            .unwrap_or_default();
+        let remaining = committed
+            .checked_sub(amount)
+            .map_err(|_| ContractError::SettleExceedsCommitted { committed })?;
+        SKYWAY_COMMITTED.save(deps.storage, denom.clone(), &remaining)?;
+        Ok(Response::new()
+            .add_attribute("action", "settle_skyway_sends")
+            .add_attribute("denom", denom.clone())
+            .add_attribute("amount", amount)
+            .add_event(ContractEvent::SkywaySendsSettled { denom, amount }))
+    }
+
+    pub fn emergency_withdraw(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+        amount: Uint128,
+        to: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        let withdrawal = EmergencyWithdrawal {
+            denom,
+            amount,
+            to: deps.api.addr_validate(&to)?,
+            executable_at: env.block.time.plus_seconds(EMERGENCY_WITHDRAW_DELAY),
+        };
+        PENDING_EMERGENCY_WITHDRAWAL.save(deps.storage, &withdrawal)?;
+        Ok(Response::new()
+            .add_attribute("action", "emergency_withdraw")
+            .add_attribute("denom", withdrawal.denom.clone())
+            .add_attribute("amount", withdrawal.amount)
+            .add_attribute("to", withdrawal.to.clone())
+            .add_attribute("executable_at", withdrawal.executable_at.to_string())
+            .add_event(ContractEvent::EmergencyWithdrawalScheduled {
+                denom: withdrawal.denom,
+                amount: withdrawal.amount,
+                to: withdrawal.to,
+                executable_at: withdrawal.executable_at,
+            }))
+    }
+
+    pub fn execute_emergency_withdraw(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let withdrawal = PENDING_EMERGENCY_WITHDRAWAL
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingWithdrawal {})?;
+        if env.block.time < withdrawal.executable_at {
+            return Err(ContractError::Timelocked {
+                executable_at: withdrawal.executable_at,
+            });
+        }
+        check_unclaimed_fees(deps.as_ref(), &env, &withdrawal.denom, withdrawal.amount)?;
+        use_fee_paid_deposits(deps.storage, &withdrawal.denom, withdrawal.amount)?;
+        PENDING_EMERGENCY_WITHDRAWAL.remove(deps.storage);
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: withdrawal.to.to_string(),
+                amount: vec![Coin {
+                    denom: withdrawal.denom.clone(),
+                    amount: withdrawal.amount,
+                }],
+            })
+            .add_attribute("action", "execute_emergency_withdraw")
+            .add_attribute("denom", withdrawal.denom.clone())
+            .add_attribute("amount", withdrawal.amount)
+            .add_attribute("to", withdrawal.to.clone())
+            .add_event(ContractEvent::EmergencyWithdrawalExecuted {
+                denom: withdrawal.denom,
+                amount: withdrawal.amount,
+                to: withdrawal.to,
+            }))
+    }
+
+    pub fn cancel_emergency_withdraw(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let withdrawal = PENDING_EMERGENCY_WITHDRAWAL
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoPendingWithdrawal {})?;
+        PENDING_EMERGENCY_WITHDRAWAL.remove(deps.storage);
+        Ok(Response::new()
+            .add_attribute("action", "cancel_emergency_withdraw")
+            .add_event(ContractEvent::EmergencyWithdrawalCancelled {
+                denom: withdrawal.denom,
+                amount: withdrawal.amount,
+                to: withdrawal.to,
+            }))
+    }
+
+    pub fn sweep_stray_funds(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+        to: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        // Palomagold and registered tokens can only leave through Skyway or an
+        // emergency withdrawal.
+        let registered = TOKENS
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .any(|(_, token)| token.denom == denom);
+        if denom == state.palomagold_denom || registered {
+            return Err(ContractError::NotStrayDenom { denom });
+        }
+        let to = deps.api.addr_validate(&to)?;
+        let balance = deps
+            .querier
+            .query_balance(env.contract.address, denom.clone())?;
+        if balance.amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: to.to_string(),
+                amount: vec![balance.clone()],
+            })
+            .add_attribute("action", "sweep_stray_funds")
+            .add_attribute("denom", denom.clone())
+            .add_attribute("amount", balance.amount)
+            .add_attribute("to", to.clone())
+            .add_event(ContractEvent::StrayFundsSwept {
+                denom,
+                amount: balance.amount,
+                to,
+            }))
+    }
+
+    pub fn set_paloma(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        // ACTION: Implement SetPaloma
+        let state = STATE.load(deps.storage)?;
+        assert!(info.sender == state.owner, "Unauthorized");
+        let job_id = CHAIN_SETTINGS
+            .load(deps.storage, chain_id.clone())?
+            .job(JobPurpose::Admin)
+            .to_string();
+
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                "set_paloma".to_string(),
+                vec![Function {
+                    name: "set_paloma".to_string(),
+                    inputs: vec![],
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        Ok(Response::new()
+            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: job_id.clone(),
+                    payload: Binary::new(
+                        contract
+                            .function("set_paloma")
+                            .unwrap()
+                            .encode_input(&[])
+                            .unwrap(),
+                    ),
+                }),
+            }))
+            .add_attribute("action", "set_paloma")
+            .add_event(ContractEvent::PalomaSetForwarded { chain_id, job_id }))
+    }
+
+    pub fn update_refund_wallet(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        new_refund_wallet: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        assert!(state.owner == info.sender, "Unauthorized");
+        let job_id = CHAIN_SETTINGS
+            .load(deps.storage, chain_id.clone())?
+            .job(JobPurpose::Admin)
+            .to_string();
+        let new_refund_wallet_address: Address =
+            Address::from_str(new_refund_wallet.as_str()).unwrap();
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                "update_refund_wallet".to_string(),
+                vec![Function {
+                    name: "update_refund_wallet".to_string(),
+                    inputs: vec![Param {
+                        name: "new_refund_wallet".to_string(),
+                        kind: ParamType::Address,
+                        internal_type: None,
+                    }],
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        Ok(Response::new()
+            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: job_id.clone(),
+                    payload: Binary::new(
+                        contract
+                            .function("update_refund_wallet")
+                            .unwrap()
+                            .encode_input(&[Token::Address(new_refund_wallet_address)])
+                            .unwrap(),
+                    ),
+                }),
+            }))
+            .add_attribute("action", "update_refund_wallet")
+            .add_event(ContractEvent::RefundWalletUpdateForwarded {
+                chain_id,
+                job_id,
+                refund_wallet: new_refund_wallet,
+            }))
+    }
+
+    pub fn update_gas_fee(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        new_gas_fee: Uint256,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        // ACTION: Implement UpdateGasFee
+        let state = STATE.load(deps.storage)?;
+        assert!(info.sender == state.owner, "Unauthorized");
+        let job_id = CHAIN_SETTINGS
+            .load(deps.storage, chain_id.clone())?
+            .job(JobPurpose::Fee)
+            .to_string();
+        let overridden = check_fee(deps.storage, &chain_id, FeeKind::GasFee, new_gas_fee)?;
+        let value = new_gas_fee.to_string();
+        let new_gas_fee: Uint = Uint::from_big_endian(&new_gas_fee.to_be_bytes());
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                "update_gas_fee".to_string(),
+                vec![Function {
+                    name: "update_gas_fee".to_string(),
+                    inputs: vec![Param {
+                        name: "new_gas_fee".to_string(),
+                        kind: ParamType::Uint(256),
+                        internal_type: None,
+                    }],
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        Ok(Response::new()
+            .add_message(CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                create_job: None,
+                execute_job: Some(ExecuteJob {
+                    job_id: job_id.clone(),
+                    payload: Binary::new(
+                        contract
+                            .function("update_gas_fee")
+                            .unwrap()
+                            .encode_input(&[Token::Uint(new_gas_fee)])
+                            .unwrap(),
+                    ),
+                }),
+            }))
+            .add_attribute("action", "update_gas_fee")
+            .add_event(ContractEvent::FeeUpdateForwarded {
+                chain_id,
+                job_id,
+                fee: "gas_fee".to_string(),
+                value,
+                overridden,
+            }))
+    }
+
+    pub fn update_service_fee_collector(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        new_service_fee_collector: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        // ACTION: Implement UpdateServiceFeeCollector
+        let state = STATE.load(deps.storage)?;
+        assert!(info.sender == state.owner, "Unauthorized");
+        let (message, event) =
+            service_fee_collector_update(deps.storage, chain_id, &new_service_fee_collector)?;
+        Ok(Response::new()
+            .add_message(message)
+            .add_attribute("action", "update_service_fee_collector")
+            .add_event(event))
+    }
+
+    pub fn update_service_fee(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        new_service_fee: Uint256,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        // ACTION: Implement UpdateServiceFee
+        let state = STATE.load(deps.storage)?;
+        assert!(info.sender == state.owner, "Unauthorized");
+        let (message, event) = service_fee_update(deps.storage, chain_id, new_service_fee)?;
+        Ok(Response::new()
+            .add_message(message)
+            .add_attribute("action", "update_service_fee")
+            .add_event(event))
+    }
+
+    pub fn broadcast_service_fee_collector(
+        deps: DepsMut,
+        info: MessageInfo,
+        chains: ChainTargets,
+        new_service_fee_collector: String,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let chain_ids = target_chains(deps.storage, chains)?;
+        let mut response = Response::new()
+            .add_attribute("action", "broadcast_service_fee_collector")
+            .add_attribute("count", chain_ids.len().to_string());
+        for chain_id in chain_ids {
+            let (message, event) =
+                service_fee_collector_update(deps.storage, chain_id, &new_service_fee_collector)?;
+            response = response.add_message(message).add_event(event);
+        }
+        Ok(response)
+    }
+
+    pub fn broadcast_service_fee(
+        deps: DepsMut,
+        info: MessageInfo,
+        chains: ChainTargets,
+        new_service_fee: Uint256,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let chain_ids = target_chains(deps.storage, chains)?;
+        let mut response = Response::new()
+            .add_attribute("action", "broadcast_service_fee")
+            .add_attribute("count", chain_ids.len().to_string());
+        for chain_id in chain_ids {
+            let (message, event) = service_fee_update(deps.storage, chain_id, new_service_fee)?;
+            response = response.add_message(message).add_event(event);
+        }
+        Ok(response)
+    }
+
+    /// Resolves broadcast targets to registered chain IDs, without duplicates.
+    fn target_chains(
+        storage: &dyn Storage,
+        chains: ChainTargets,
+    ) -> Result<Vec<String>, ContractError> {
+        let chain_ids = match chains {
+            ChainTargets::All => CHAIN_SETTINGS
+                .keys(storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?,
+            ChainTargets::Chains(chain_ids) => {
+                let mut unique: Vec<String> = Vec::with_capacity(chain_ids.len());
+                for chain_id in chain_ids {
+                    if !CHAIN_SETTINGS.has(storage, chain_id.clone()) {
+                        return Err(ContractError::UnknownChain { chain_id });
+                    }
+                    if !unique.contains(&chain_id) {
+                        unique.push(chain_id);
+                    }
+                }
+                unique
+            }
+        };
+        if chain_ids.is_empty() {
+            return Err(ContractError::EmptyBatch {});
+        }
+        Ok(chain_ids)
+    }
+
+    fn service_fee_collector_update(
+        storage: &dyn Storage,
+        chain_id: String,
+        new_service_fee_collector: &str,
+    ) -> Result<(CosmosMsg<PalomaMsg>, ContractEvent), ContractError> {
+        let job_id = CHAIN_SETTINGS
+            .may_load(storage, chain_id.clone())?
+            .ok_or_else(|| ContractError::UnknownChain {
+                chain_id: chain_id.clone(),
+            })?
+            .job(JobPurpose::Fee)
+            .to_string();
+        let (new_service_fee_collector, value) = parse_evm_address(new_service_fee_collector)?;
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                "update_service_fee_collector".to_string(),
+                vec![Function {
+                    name: "update_service_fee_collector".to_string(),
+                    inputs: vec![Param {
+                        name: "new_service_fee_collector".to_string(),
+                        kind: ParamType::Address,
+                        internal_type: None,
+                    }],
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        let message = CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+            create_job: None,
+            execute_job: Some(ExecuteJob {
+                job_id: job_id.clone(),
+                payload: Binary::new(
+                    contract
+                        .function("update_service_fee_collector")
+                        .unwrap()
+                        .encode_input(&[Token::Address(new_service_fee_collector)])
+                        .unwrap(),
+                ),
+            }),
+        });
+        let event = ContractEvent::FeeUpdateForwarded {
+            chain_id,
+            job_id,
+            fee: "service_fee_collector".to_string(),
+            value,
+            overridden: false,
+        };
+        Ok((message, event))
+    }
+
+    fn service_fee_update(
+        storage: &mut dyn Storage,
+        chain_id: String,
+        new_service_fee: Uint256,
+    ) -> Result<(CosmosMsg<PalomaMsg>, ContractEvent), ContractError> {
+        let job_id = CHAIN_SETTINGS
+            .may_load(storage, chain_id.clone())?
+            .ok_or_else(|| ContractError::UnknownChain {
+                chain_id: chain_id.clone(),
+            })?
+            .job(JobPurpose::Fee)
+            .to_string();
+        let overridden = check_fee(storage, &chain_id, FeeKind::ServiceFee, new_service_fee)?;
+        let value = new_service_fee.to_string();
+        let new_service_fee: Uint = Uint::from_big_endian(&new_service_fee.to_be_bytes());
+        #[allow(deprecated)]
+        let contract: Contract = Contract {
+            constructor: None,
+            functions: BTreeMap::from_iter(vec![(
+                "update_service_fee".to_string(),
+                vec![Function {
+                    name: "update_service_fee".to_string(),
+                    inputs: vec![Param {
+                        name: "new_service_fee".to_string(),
+                        kind: ParamType::Uint(256),
+                        internal_type: None,
+                    }],
+                    outputs: Vec::new(),
+                    constant: None,
+                    state_mutability: StateMutability::NonPayable,
+                }],
+            )]),
+            events: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            receive: false,
+            fallback: false,
+        };
+        let message = CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+            create_job: None,
+            execute_job: Some(ExecuteJob {
+                job_id: job_id.clone(),
+                payload: Binary::new(
+                    contract
+                        .function("update_service_fee")
+                        .unwrap()
+                        .encode_input(&[Token::Uint(new_service_fee)])
+                        .unwrap(),
+                ),
+            }),
+        });
+        let event = ContractEvent::FeeUpdateForwarded {
+            chain_id,
+            job_id,
+            fee: "service_fee".to_string(),
+            value,
+            overridden,
+        };
+        Ok((message, event))
+    }
+
+    /// Checks a fee about to be forwarded against the bounds of the chain and
+    /// records it as the last forwarded value. A value approved as an override
+    /// skips the bounds and is used up. Returns whether an override was used.
+    fn check_fee(
+        storage: &mut dyn Storage,
+        chain_id: &str,
+        fee: FeeKind,
+        value: Uint256,
+    ) -> Result<bool, ContractError> {
+        let key = (chain_id.to_string(), fee.as_str().to_string());
+        let overridden = FEE_OVERRIDES.may_load(storage, key.clone())? == Some(value);
+        if overridden {
+            FEE_OVERRIDES.remove(storage, key.clone());
+        } else if let Some(bounds) = FEE_BOUNDS.may_load(storage, key.clone())? {
+            if value < bounds.min || value > bounds.max {
+                return Err(ContractError::FeeOutOfBounds {
+                    fee: fee.as_str().to_string(),
+                    min: bounds.min,
+                    max: bounds.max,
+                });
+            }
+            let last = FORWARDED_FEES.may_load(storage, key.clone())?;
+            if let (Some(max_change), Some(last)) = (bounds.max_change, last) {
+                // The change from zero has no ratio; the bounds alone apply.
+                if !last.is_zero() {
+                    let change = value.abs_diff(last);
+                    let too_large = Decimal256::checked_from_ratio(change, last)
+                        .map_or(true, |ratio| ratio > max_change);
+                    if too_large {
+                        return Err(ContractError::FeeChangeTooLarge {
+                            fee: fee.as_str().to_string(),
+                            max_change,
+                        });
+                    }
+                }
+            }
+        }
+        FORWARDED_FEES.save(storage, key, &value)?;
+        Ok(overridden)
+    }
+
+    pub fn set_fee_bounds(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        fee: FeeKind,
+        bounds: Option<FeeBounds>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        if !CHAIN_SETTINGS.has(deps.storage, chain_id.clone()) {
+            return Err(ContractError::UnknownChain { chain_id });
+        }
+        let key = (chain_id.clone(), fee.as_str().to_string());
+        match &bounds {
+            Some(bounds) => {
+                if bounds.min > bounds.max {
+                    return Err(ContractError::InvalidFeeBounds {});
+                }
+                FEE_BOUNDS.save(deps.storage, key, bounds)?;
+            }
+            None => FEE_BOUNDS.remove(deps.storage, key),
+        }
+        Ok(Response::new()
+            .add_attribute("action", "set_fee_bounds")
+            .add_event(ContractEvent::FeeBoundsSet {
+                chain_id,
+                fee,
+                bounds,
+            }))
+    }
+
+    pub fn set_fee_override_approver(
+        deps: DepsMut,
+        info: MessageInfo,
+        approver: Option<String>,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        if info.sender != state.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+        let approver = approver
+            .map(|approver| deps.api.addr_validate(&approver))
+            .transpose()?;
+        match &approver {
+            Some(approver) => {
+                if *approver == state.owner {
+                    return Err(ContractError::ApproverIsOwner {});
+                }
+                FEE_OVERRIDE_APPROVER.save(deps.storage, approver)?;
+            }
+            None => FEE_OVERRIDE_APPROVER.remove(deps.storage),
+        }
+        Ok(Response::new()
+            .add_attribute("action", "set_fee_override_approver")
+            .add_event(ContractEvent::FeeOverrideApproverSet { approver }))
+    }
+
+    pub fn approve_fee_override(
+        deps: DepsMut,
+        info: MessageInfo,
+        chain_id: String,
+        fee: FeeKind,
+        value: Uint256,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        let approver = FEE_OVERRIDE_APPROVER.may_load(deps.storage)?;
+        if approver.as_ref() != Some(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+        if !CHAIN_SETTINGS.has(deps.storage, chain_id.clone()) {
+            return Err(ContractError::UnknownChain { chain_id });
+        }
+        FEE_OVERRIDES.save(
+            deps.storage,
+            (chain_id.clone(), fee.as_str().to_string()),
+            &value,
+        )?;
+        Ok(Response::new()
+            .add_attribute("action", "approve_fee_override")
+            .add_event(ContractEvent::FeeOverrideApproved {
+                chain_id,
+                fee,
+                value,
+                approver: info.sender,
+            }))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => query::config(deps),
+        QueryMsg::ContractInfo {} => query::contract_info(deps),
+        QueryMsg::PalomagoldBalance { token } => query::palomagold_balance(deps, env, token),
+        QueryMsg::Token { token_key } => query::token(deps, token_key),
+        QueryMsg::Treasury {} => query::treasury(deps, env),
+        QueryMsg::ConversionRatio { chain_id, epoch } => {
+            query::conversion_ratio(deps, chain_id, epoch)
+        }
+        QueryMsg::MigrationWindow { chain_id } => query::migration_window(deps, env, chain_id),
+        QueryMsg::Vesting {
+            chain_id,
+            recipient,
+        } => query::vesting(deps, env, chain_id, recipient),
+        QueryMsg::RecipientTotals {
+            chain_id,
+            recipient,
+            token,
+        } => query::recipient_totals(deps, chain_id, recipient, token),
+        QueryMsg::RecipientHistory {
+            chain_id,
+            recipient,
+            start_after,
+            limit,
+        } => query::recipient_history(deps, chain_id, recipient, start_after, limit),
+        QueryMsg::Solvency { token } => query::solvency(deps, token),
+        QueryMsg::Release { chain_id, nonce } => query::release(deps, chain_id, nonce),
+        QueryMsg::PendingEmergencyWithdrawal {} => query::pending_emergency_withdrawal(deps),
+        QueryMsg::LegacyConversion {} => query::legacy_conversion(deps),
+        QueryMsg::ExportState {
+            namespace,
+            start_after,
+            limit,
+        } => query::export_state(deps, namespace, start_after, limit),
+        QueryMsg::Denylist {
+            kind,
+            start_after,
+            limit,
+        } => query::denylist(deps, kind, start_after, limit),
+        QueryMsg::Hooks { event } => query::hooks(deps, event),
+        QueryMsg::JobRotation { chain_id } => query::job_rotation(deps, chain_id),
+        QueryMsg::FeeStatus { chain_id } => query::fee_status(deps, chain_id),
+        QueryMsg::FeeLedger { collector } => query::fee_ledger(deps, collector),
+        QueryMsg::Quote {
+            chain_id,
+            amount,
+            token,
+        } => query::quote(deps, chain_id, amount, token),
+    }
+}
+
+pub mod query {
+    use cosmwasm_std::{to_json_binary, StdError};
+    use std::str::FromStr;
+
+    use super::*;
+    use cosmwasm_std::{Addr, Coin, Order, Record, Uint128, Uint256};
+    use cw_storage_plus::{Bound, KeyDeserialize, PrimaryKey};
+
+    use crate::msg::{
+        BalanceResponse, ConfigResponse, ConversionRatioResponse, DenylistKind, DenylistResponse,
+        ExportStateResponse, FeeLedgerResponse, FeeStatus, FeeStatusResponse, HooksResponse,
+        JobRotationResponse, MigrationWindowResponse, QuoteResponse, RecipientHistoryResponse,
+        RecipientRelease, RecipientTotalsResponse, SolvencyResponse, StateNamespace, StateRecord,
+        TreasuryBalance, TreasuryResponse, VestingResponse,
+    };
+    use crate::state::{
+        releases, FeeKind, HookEvent, JobPurpose, ACCRUED_FEES, CHAIN_SETTINGS, CONTRACT_NONCE,
+        CONVERSION_RATIOS, CURRENT_EPOCH, DENIED_EVM_ADDRESSES, DENIED_PALOMA_ADDRESSES,
+        FEE_BOUNDS, FEE_OVERRIDES, FEE_OVERRIDE_APPROVER, FEE_PAID_DEPOSITS, FORWARDED_FEES, HOOKS,
+        JOB_ROTATIONS, LEGACY_CONVERSION, MIGRATION_WINDOWS, PALOMA_FEE,
+        PENDING_EMERGENCY_WITHDRAWAL, RECIPIENT_TOTALS, RELEASED_PER_CHAIN, RESERVED_RELEASES,
+        SEALED, SKYWAY_COMMITTED, SUPPLY_ALLOWANCE, SUPPLY_TOTALS, TOKENS, VESTING_SCHEDULES,
+        WINDOW_STATUS, WITHDRAW_TIMESTAMP,
+    };
+
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
+
+    pub fn config(deps: Deps) -> StdResult<Binary> {
+        let state = STATE.load(deps.storage)?;
+        to_json_binary(&ConfigResponse {
+            owner: state.owner,
+            palomagold_denom: state.palomagold_denom,
+            sealed: SEALED.may_load(deps.storage)?.unwrap_or_default(),
+            fee_override_approver: FEE_OVERRIDE_APPROVER.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn contract_info(deps: Deps) -> StdResult<Binary> {
+        to_json_binary(&cw2::get_contract_version(deps.storage)?)
+    }
+
+    pub fn palomagold_balance(deps: Deps, env: Env, token: Option<String>) -> StdResult<Binary> {
+        to_json_binary(&BalanceResponse {
+            balance: deps
+                .querier
+                .query_balance(env.contract.address, denom(deps, token)?)?
+                .amount,
+        })
+    }
+
+    pub fn token(deps: Deps, token_key: String) -> StdResult<Binary> {
+        to_json_binary(&TOKENS.load(deps.storage, token_key)?)
+    }
+
+    pub fn conversion_ratio(deps: Deps, chain_id: String, epoch: Option<u64>) -> StdResult<Binary> {
+        let epoch = match epoch {
+            Some(epoch) => epoch,
+            None => CURRENT_EPOCH
+                .may_load(deps.storage, chain_id.clone())?
+                .ok_or_else(|| StdError::not_found("current epoch"))?,
+        };
+        to_json_binary(&ConversionRatioResponse {
+            epoch,
+            conversion_ratio: CONVERSION_RATIOS.load(deps.storage, (chain_id, epoch))?,
+        })
+    }
+
+    pub fn migration_window(deps: Deps, env: Env, chain_id: String) -> StdResult<Binary> {
+        let window = MIGRATION_WINDOWS.may_load(deps.storage, chain_id)?;
+        let status = window.as_ref().map(|window| window.status(env.block.time));
+        to_json_binary(&MigrationWindowResponse { window, status })
+    }
+
+    pub fn vesting(deps: Deps, env: Env, chain_id: String, recipient: String) -> StdResult<Binary> {
+        let schedule =
+            VESTING_SCHEDULES.load(deps.storage, (chain_id, canonical_recipient(&recipient)?))?;
+        let vested = schedule.vested(env.block.time);
+        to_json_binary(&VestingResponse {
+            releasable: vested - schedule.released,
+            vested,
+            schedule,
+        })
+    }
+
+    pub fn recipient_totals(
+        deps: Deps,
+        chain_id: String,
+        recipient: String,
+        token: Option<String>,
+    ) -> StdResult<Binary> {
+        let totals = RECIPIENT_TOTALS
+            .may_load(
+                deps.storage,
+                (
+                    chain_id.clone(),
+                    canonical_recipient(&recipient)?,
+                    denom(deps, token)?,
+                ),
+            )?
+            .unwrap_or_default();
+        to_json_binary(&RecipientTotalsResponse {
+            released: totals.released,
+            bridged: totals.bridged,
+            cap: CHAIN_SETTINGS.load(deps.storage, chain_id)?.recipient_cap,
+        })
+    }
+
+    /// Pages through the releases of a recipient in the order of their
+    /// storage keys, the nonces as decimal strings.
+    pub fn recipient_history(
+        deps: Deps,
+        chain_id: String,
+        recipient: String,
+        start_after: Option<Uint256>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start =
+            start_after.map(|nonce| Bound::exclusive((chain_id.clone(), nonce.to_string())));
+        let releases = releases()
+            .idx
+            .recipient
+            .prefix((chain_id, canonical_recipient(&recipient)?))
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let ((_, nonce), record) = item?;
+                Ok(RecipientRelease {
+                    nonce: Uint256::from_str(&nonce)?,
+                    amount: record.amount,
+                    scheduled_at: record.scheduled_at,
+                    expires_at: record.expires_at,
+                    status: record.status,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        to_json_binary(&RecipientHistoryResponse { releases })
+    }
+
+    pub fn solvency(deps: Deps, token: Option<String>) -> StdResult<Binary> {
+        let denom = denom(deps, token)?;
+        let supply = SUPPLY_TOTALS
+            .may_load(deps.storage, denom.clone())?
+            .unwrap_or_default();
+        let allowance = SUPPLY_ALLOWANCE.may_load(deps.storage, denom.clone())?;
+        let released_per_chain = RELEASED_PER_CHAIN
+            .prefix(denom)
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        to_json_binary(&SolvencyResponse {
+            headroom: allowance.map(|allowance| {
+                (Uint256::from(supply.bridged) + allowance).saturating_sub(supply.released)
+            }),
+            received: supply.received,
+            bridged: supply.bridged,
+            released: supply.released,
+            released_per_chain,
+            allowance,
+        })
+    }
+
+    pub fn legacy_conversion(deps: Deps) -> StdResult<Binary> {
+        to_json_binary(&LEGACY_CONVERSION.may_load(deps.storage)?)
+    }
+
+    pub fn export_state(
+        deps: Deps,
+        namespace: StateNamespace,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let storage = deps.storage;
+        let (records, next) = match namespace {
+            StateNamespace::State => item_page(STATE.may_load(storage)?.map(StateRecord::State)),
+            StateNamespace::ChainSettings => page::<String, _>(
+                CHAIN_SETTINGS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, chain_setting| StateRecord::ChainSetting {
+                    chain_id,
+                    chain_setting,
+                },
+            )?,
+            StateNamespace::WithdrawTimestamps => page::<(String, String), _>(
+                WITHDRAW_TIMESTAMP.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, nonce), timestamp| StateRecord::WithdrawTimestamp {
+                    chain_id,
+                    nonce,
+                    timestamp,
+                },
+            )?,
+            StateNamespace::ConversionRatios => page::<(String, u64), _>(
+                CONVERSION_RATIOS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, epoch), conversion_ratio| StateRecord::ConversionRatio {
+                    chain_id,
+                    epoch,
+                    conversion_ratio,
+                },
+            )?,
+            StateNamespace::CurrentEpochs => page::<String, _>(
+                CURRENT_EPOCH.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, epoch| StateRecord::CurrentEpoch { chain_id, epoch },
+            )?,
+            StateNamespace::MigrationWindows => page::<String, _>(
+                MIGRATION_WINDOWS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, window| StateRecord::MigrationWindow { chain_id, window },
+            )?,
+            StateNamespace::WindowStatuses => page::<String, _>(
+                WINDOW_STATUS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, status| StateRecord::WindowStatus { chain_id, status },
+            )?,
+            StateNamespace::VestingSchedules => page::<(String, String), _>(
+                VESTING_SCHEDULES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, recipient), schedule| StateRecord::VestingSchedule {
+                    chain_id,
+                    recipient,
+                    schedule,
+                },
+            )?,
+            StateNamespace::ContractNonces => page::<String, _>(
+                CONTRACT_NONCE.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, counter| StateRecord::ContractNonce { chain_id, counter },
+            )?,
+            StateNamespace::RecipientTotals => page::<(String, String, String), _>(
+                RECIPIENT_TOTALS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, recipient, denom), totals| StateRecord::RecipientTotals {
+                    chain_id,
+                    recipient,
+                    denom,
+                    totals,
+                },
+            )?,
+            StateNamespace::Releases => page::<(String, String), _>(
+                releases().range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(_, nonce), record| StateRecord::Release { nonce, record },
+            )?,
+            StateNamespace::SupplyTotals => page::<String, _>(
+                SUPPLY_TOTALS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |denom, totals| StateRecord::SupplyTotals { denom, totals },
+            )?,
+            StateNamespace::ReleasedPerChain => page::<(String, String), _>(
+                RELEASED_PER_CHAIN.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(denom, chain_id), released| StateRecord::ReleasedPerChain {
+                    denom,
+                    chain_id,
+                    released,
+                },
+            )?,
+            StateNamespace::SupplyAllowances => page::<String, _>(
+                SUPPLY_ALLOWANCE.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |denom, allowance| StateRecord::SupplyAllowance { denom, allowance },
+            )?,
+            StateNamespace::DeniedEvmAddresses => page::<String, _>(
+                DENIED_EVM_ADDRESSES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |address, _| StateRecord::DeniedEvmAddress { address },
+            )?,
+            StateNamespace::DeniedPalomaAddresses => page::<&Addr, _>(
+                DENIED_PALOMA_ADDRESSES.range_raw(
+                    storage,
+                    after(start_after),
+                    None,
+                    Order::Ascending,
+                ),
+                limit,
+                |address, _| StateRecord::DeniedPalomaAddress { address },
+            )?,
+            StateNamespace::PendingEmergencyWithdrawal => item_page(
+                PENDING_EMERGENCY_WITHDRAWAL
+                    .may_load(storage)?
+                    .map(StateRecord::PendingEmergencyWithdrawal),
+            ),
+            StateNamespace::Tokens => page::<String, _>(
+                TOKENS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |token_key, token| StateRecord::Token { token_key, token },
+            )?,
+            StateNamespace::ReservedReleases => page::<String, _>(
+                RESERVED_RELEASES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |denom, reserved| StateRecord::ReservedReleases { denom, reserved },
+            )?,
+            StateNamespace::SkywayCommitted => page::<String, _>(
+                SKYWAY_COMMITTED.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |denom, committed| StateRecord::SkywayCommitted { denom, committed },
+            )?,
+            StateNamespace::LegacyConversion => item_page(
+                LEGACY_CONVERSION
+                    .may_load(storage)?
+                    .map(StateRecord::LegacyConversion),
+            ),
+            StateNamespace::Hooks => page::<(HookEvent, &Addr), _>(
+                HOOKS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(event, contract), _| StateRecord::Hook { event, contract },
+            )?,
+            StateNamespace::JobRotations => page::<String, _>(
+                JOB_ROTATIONS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |chain_id, rotation| StateRecord::JobRotation { chain_id, rotation },
+            )?,
+            StateNamespace::FeeBounds => page::<(String, String), _>(
+                FEE_BOUNDS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, fee), bounds| StateRecord::FeeBounds {
+                    chain_id,
+                    fee,
+                    bounds,
+                },
+            )?,
+            StateNamespace::ForwardedFees => page::<(String, String), _>(
+                FORWARDED_FEES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, fee), value| StateRecord::ForwardedFee {
+                    chain_id,
+                    fee,
+                    value,
+                },
+            )?,
+            StateNamespace::FeeOverrides => page::<(String, String), _>(
+                FEE_OVERRIDES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(chain_id, fee), value| StateRecord::FeeOverride {
+                    chain_id,
+                    fee,
+                    value,
+                },
+            )?,
+            StateNamespace::FeeOverrideApprover => item_page(
+                FEE_OVERRIDE_APPROVER
+                    .may_load(storage)?
+                    .map(StateRecord::FeeOverrideApprover),
+            ),
+            StateNamespace::PalomaFee => {
+                item_page(PALOMA_FEE.may_load(storage)?.map(StateRecord::PalomaFee))
+            }
+            StateNamespace::AccruedFees => page::<(&Addr, String), _>(
+                ACCRUED_FEES.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |(collector, denom), amount| StateRecord::AccruedFee {
+                    collector,
+                    denom,
+                    amount,
+                },
+            )?,
+            StateNamespace::FeePaidDeposits => page::<String, _>(
+                FEE_PAID_DEPOSITS.range_raw(storage, after(start_after), None, Order::Ascending),
+                limit,
+                |denom, amount| StateRecord::FeePaidDeposit { denom, amount },
+            )?,
+        };
+        to_json_binary(&ExportStateResponse { records, next })
+    }
+
+    /// Export cursors are raw storage keys, so any namespace pages the same way.
+    fn after<'a, K: PrimaryKey<'a>>(start_after: Option<Binary>) -> Option<Bound<'a, K>> {
+        start_after.map(|key| Bound::ExclusiveRaw(key.to_vec()))
+    }
+
+    type Page = (Vec<StateRecord>, Option<Binary>);
+
+    fn page<K: KeyDeserialize, T>(
+        entries: impl Iterator<Item = StdResult<Record<T>>>,
+        limit: usize,
+        record: impl Fn(K::Output, T) -> StateRecord,
+    ) -> StdResult<Page> {
+        let entries = entries.take(limit).collect::<StdResult<Vec<_>>>()?;
+        let next = match entries.last() {
+            Some((key, _)) if entries.len() == limit => Some(Binary::from(key.clone())),
+            _ => None,
+        };
+        let records = entries
+            .into_iter()
+            .map(|(key, value)| Ok(record(K::from_vec(key)?, value)))
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok((records, next))
+    }
+
+    fn item_page(record: Option<StateRecord>) -> Page {
+        (record.into_iter().collect(), None)
+    }
+
+    pub fn treasury(deps: Deps, env: Env) -> StdResult<Binary> {
+        let palomagold = (None, STATE.load(deps.storage)?.palomagold_denom);
+        let tokens = TOKENS
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(token_key, token)| (Some(token_key), token.denom)));
+        let balances = std::iter::once(Ok(palomagold))
+            .chain(tokens)
+            .map(|item| {
+                let (token, denom) = item?;
+                let balance = deps
+                    .querier
+                    .query_balance(env.contract.address.clone(), denom.clone())?
+                    .amount;
+                let committed = SKYWAY_COMMITTED
+                    .may_load(deps.storage, denom.clone())?
+                    .unwrap_or_default();
+                let reserved = RESERVED_RELEASES
+                    .may_load(deps.storage, denom.clone())?
+                    .unwrap_or_default();
+                let fees = execute::accrued_fees(deps.storage, &denom)?;
+                Ok(TreasuryBalance {
+                    token,
+                    denom,
+                    balance,
+                    committed,
+                    reserved,
+                    fees,
+                    free: Uint256::from(balance)
+                        .saturating_sub(reserved)
+                        .saturating_sub(fees.into()),
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        to_json_binary(&TreasuryResponse { balances })
+    }
+
+    pub fn denylist(
+        deps: Deps,
+        kind: DenylistKind,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let addresses = match kind {
+            DenylistKind::Evm => {
+                let start = start_after
+                    .map(|address| canonical_recipient(&address))
+                    .transpose()?
+                    .map(Bound::exclusive);
+                DENIED_EVM_ADDRESSES
+                    .keys(deps.storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .collect::<StdResult<Vec<_>>>()?
+            }
+            DenylistKind::Paloma => {
+                let start_after = start_after
+                    .map(|address| deps.api.addr_validate(&address))
+                    .transpose()?;
+                DENIED_PALOMA_ADDRESSES
+                    .keys(
+                        deps.storage,
+                        start_after.as_ref().map(Bound::exclusive),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(|address| address.map(String::from))
+                    .collect::<StdResult<Vec<_>>>()?
+            }
+        };
+        to_json_binary(&DenylistResponse { addresses })
+    }
+
+    pub fn job_rotation(deps: Deps, chain_id: String) -> StdResult<Binary> {
+        let chain_setting = CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        let rotation = JOB_ROTATIONS.may_load(deps.storage, chain_id)?;
+        let release_job_id = match &rotation {
+            Some(rotation) => rotation.new_job_id.clone(),
+            None => chain_setting.job(JobPurpose::Release).to_string(),
+        };
+        to_json_binary(&JobRotationResponse {
+            release_job_id,
+            rotation,
+        })
+    }
+
+    pub fn fee_status(deps: Deps, chain_id: String) -> StdResult<Binary> {
+        CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        let status = |fee: FeeKind| -> StdResult<FeeStatus> {
+            let key = (chain_id.clone(), fee.as_str().to_string());
+            Ok(FeeStatus {
+                bounds: FEE_BOUNDS.may_load(deps.storage, key.clone())?,
+                last_forwarded: FORWARDED_FEES.may_load(deps.storage, key.clone())?,
+                approved_override: FEE_OVERRIDES.may_load(deps.storage, key)?,
+            })
+        };
+        to_json_binary(&FeeStatusResponse {
+            approver: FEE_OVERRIDE_APPROVER.may_load(deps.storage)?,
+            gas_fee: status(FeeKind::GasFee)?,
+            service_fee: status(FeeKind::ServiceFee)?,
+        })
+    }
+
+    /// Expected outcome of bridging `amount` to a chain with the Paloma-side
+    /// fee and the fees last forwarded to the chain.
+    pub fn quote(
+        deps: Deps,
+        chain_id: String,
+        amount: Uint128,
+        token: Option<String>,
+    ) -> StdResult<Binary> {
+        CHAIN_SETTINGS.load(deps.storage, chain_id.clone())?;
+        if let Some(token_key) = token.as_deref() {
+            execute::evm_token(deps.storage, token_key, &chain_id)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+        }
+        let forwarded = |fee: FeeKind| {
+            FORWARDED_FEES.may_load(deps.storage, (chain_id.clone(), fee.as_str().to_string()))
+        };
+        let gas_fee = forwarded(FeeKind::GasFee)?;
+        let service_fee_rate = forwarded(FeeKind::ServiceFee)?;
+        let paloma_fee = PALOMA_FEE
+            .may_load(deps.storage)?
+            .map(|paloma_fee| paloma_fee.fee(amount))
+            .unwrap_or_default();
+        let bridged = Uint256::from(amount - paloma_fee);
+        let service_fee = match service_fee_rate {
+            Some(rate) => bridged
+                .checked_multiply_ratio(rate, SERVICE_FEE_DENOMINATOR)
+                .map_err(|err| StdError::generic_err(err.to_string()))?,
+            None => Uint256::zero(),
+        };
+        // The gas fee is set in base units of the EVM token.
+        let gas_fee_amount = match gas_fee {
+            Some(gas_fee) => {
+                execute::to_denom_units(deps.storage, &chain_id, token.as_deref(), gas_fee)
+                    .map_err(|err| StdError::generic_err(err.to_string()))?
+            }
+            None => Uint256::zero(),
+        };
+        to_json_binary(&QuoteResponse {
+            amount,
+            paloma_fee,
+            service_fee_rate,
+            service_fee,
+            gas_fee,
+            gas_fee_amount,
+            net: bridged
+                .saturating_sub(service_fee)
+                .saturating_sub(gas_fee_amount),
+        })
+    }
+
+    pub fn fee_ledger(deps: Deps, collector: String) -> StdResult<Binary> {
+        let collector = deps.api.addr_validate(&collector)?;
+        let accrued = ACCRUED_FEES
+            .prefix(&collector)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<Vec<_>>>()?;
+        to_json_binary(&FeeLedgerResponse {
+            fee: PALOMA_FEE.may_load(deps.storage)?,
+            accrued,
+        })
+    }
+
+    pub fn hooks(deps: Deps, event: HookEvent) -> StdResult<Binary> {
+        let contracts = HOOKS
+            .prefix(event)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        to_json_binary(&HooksResponse { contracts })
+    }
+
+    pub fn release(deps: Deps, chain_id: String, nonce: Uint256) -> StdResult<Binary> {
+        to_json_binary(&releases().load(deps.storage, (chain_id, nonce.to_string()))?)
+    }
+
+    pub fn pending_emergency_withdrawal(deps: Deps) -> StdResult<Binary> {
+        to_json_binary(&PENDING_EMERGENCY_WITHDRAWAL.may_load(deps.storage)?)
+    }
+
+    fn denom(deps: Deps, token: Option<String>) -> StdResult<String> {
+        execute::token_denom(deps.storage, token.as_deref())
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    fn canonical_recipient(recipient: &str) -> StdResult<String> {
+        execute::parse_evm_address(recipient)
+            .map(|(_, canonical_address)| canonical_address)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{ContractEvent, HookMsg};
+    use crate::msg::{
+        BackfillItem, ConfigResponse, CreateJob, DenylistKind, DenylistResponse, ExecuteJob,
+        ExportStateResponse, NewJob, QuoteResponse, RecipientHistoryResponse,
+        RecipientTotalsResponse, ReleaseItem, StateNamespace, StateRecord, TreasuryBalance,
+        TreasuryResponse,
+    };
+    use crate::state::{
+        releases, ConversionRatio, FeeKind, HookEvent, MigrationWindow, ReleaseRecord,
+        ReleaseStatus, SupplyTotals, CHAIN_SETTINGS, SUPPLY_TOTALS, VESTING_SCHEDULES,
+        WITHDRAW_TIMESTAMP,
+    };
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{
+        coin, from_json, to_json_string, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal256, Env,
+        MemoryStorage, OwnedDeps, Reply, ReplyOn, SubMsgResult, Timestamp, Uint128, Uint256,
+        WasmMsg,
+    };
+
+    const RECIPIENT: &str = "0x1111111111111111111111111111111111111111";
+
+    type TestDeps = OwnedDeps<MemoryStorage, MockApi, MockQuerier>;
+
+    fn setup() -> (TestDeps, MessageInfo) {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make("owner"), &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                palomagold_denom: "pg".to_string(),
+            },
+        )
+        .unwrap();
+        register_chain(
+            &mut deps,
+            &info,
+            "1",
+            r#"{"job_id":"j","batch_release":true}"#,
+        );
+        (deps, info)
+    }
+
+    fn register_chain(deps: &mut TestDeps, info: &MessageInfo, chain_id: &str, setting: &str) {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::RegisterChain {
+                chain_id: chain_id.to_string(),
+                chain_setting: from_json(setting).unwrap(),
+                create_job: None,
+            },
+        )
+        .unwrap();
+    }
+
+    fn item(nonce: u64) -> ReleaseItem {
+        ReleaseItem {
+            recipient: RECIPIENT.to_string(),
+            amount: Uint256::from(5u8),
+            nonce: Uint256::from(nonce),
+        }
+    }
+
+    fn batch(chain_id: &str, releases: Vec<ReleaseItem>) -> ExecuteMsg {
+        ExecuteMsg::BatchRelease {
+            chain_id: chain_id.to_string(),
+            releases,
+        }
+    }
+
+    fn jobs(response: &Response<PalomaMsg>) -> Vec<String> {
+        response
+            .messages
+            .iter()
+            .filter_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                    execute_job: Some(job),
+                    ..
+                }) => Some(job.job_id.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn batch_release_sends_one_job_per_batch() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "2", r#"{"job_id":"k"}"#);
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1), item(2)]),
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string()]);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            batch("2", vec![item(1), item(2)]),
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["k".to_string(), "k".to_string()]);
+    }
+
+    #[test]
+    fn batch_release_rejects_bad_entries() {
+        let (mut deps, info) = setup();
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), batch("1", vec![])).unwrap_err();
+        assert!(matches!(err, ContractError::EmptyBatch {}));
+
+        let mut bad = item(2);
+        bad.recipient = "0x1234".to_string();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1), bad]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAddress { .. }));
+    }
+
+    #[test]
+    fn batch_release_retries_after_a_minute() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1)]),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Pending {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        execute(deps.as_mut(), env, info, batch("1", vec![item(1)])).unwrap();
+    }
+
+    fn ratio(ratio: Decimal256, palomagold_decimals: u8, evm_decimals: u8) -> ConversionRatio {
+        ConversionRatio {
+            ratio,
+            palomagold_decimals,
+            evm_decimals,
+        }
+    }
+
+    #[test]
+    fn convert_amount_scales_decimals() {
+        let one_and_a_half = Decimal256::from_ratio(3u8, 2u8);
+        assert_eq!(
+            execute::convert_amount(&ratio(one_and_a_half, 6, 18), Uint256::from(2u8)).unwrap(),
+            Uint256::from(3_000_000_000_000u64)
+        );
+        assert_eq!(
+            execute::convert_amount(
+                &ratio(Decimal256::one(), 18, 6),
+                Uint256::from(10u64.pow(12))
+            )
+            .unwrap(),
+            Uint256::one()
+        );
+    }
+
+    #[test]
+    fn convert_amount_rounds_down() {
+        let third = Decimal256::from_ratio(1u8, 3u8);
+        assert_eq!(
+            execute::convert_amount(&ratio(third, 0, 0), Uint256::from(11u8)).unwrap(),
+            Uint256::from(3u8)
+        );
+        let err = execute::convert_amount(&ratio(Decimal256::one(), 18, 6), Uint256::from(999u16))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+        let err =
+            execute::convert_amount(&ratio(Decimal256::one(), 0, 80), Uint256::one()).unwrap_err();
+        assert!(matches!(err, ContractError::ConversionOverflow {}));
+    }
+
+    #[test]
+    fn release_from_aave_uses_the_epoch_ratio() {
+        let (mut deps, info) = setup();
+        let release_from_aave = |epoch| ExecuteMsg::ReleaseFromAave {
+            chain_id: "1".to_string(),
+            recipient: RECIPIENT.to_string(),
+            aave_amount: Uint256::from(10u8),
+            nonce: Uint256::from(epoch),
+            epoch: Some(epoch),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release_from_aave(1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoConversionRatio {}));
+
+        for (epoch, value) in [(1, 2u8), (2, 3u8)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::SetConversionRatio {
+                    chain_id: "1".to_string(),
+                    epoch,
+                    conversion_ratio: ratio(Decimal256::from_ratio(value, 1u8), 0, 0),
+                },
+            )
+            .unwrap();
+        }
+        execute(deps.as_mut(), mock_env(), info, release_from_aave(1)).unwrap();
+        let record = releases()
+            .load(&deps.storage, ("1".to_string(), "1".to_string()))
+            .unwrap();
+        assert_eq!(record.amount, Uint256::from(20u8));
+    }
+
+    fn release(chain_id: &str, amount: Uint256, nonce: u64) -> ExecuteMsg {
+        ExecuteMsg::Release {
+            chain_id: chain_id.to_string(),
+            recipient: RECIPIENT.to_string(),
+            amount,
+            nonce: Uint256::from(nonce),
+            token: None,
+        }
+    }
+
+    fn env_at(seconds: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    fn window_status<T>(response: &Response<T>) -> Option<String> {
+        let event = response
+            .events
+            .iter()
+            .find(|event| event.ty == "migration_window")?;
+        event
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == "status")
+            .map(|attribute| attribute.value.clone())
+    }
+
+    #[test]
+    fn migration_window_limits_releases_and_deposits() {
+        let (mut deps, info) = setup();
+        let start = mock_env().block.time.plus_seconds(100);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetMigrationWindow {
+                chain_id: "1".to_string(),
+                window: Some(MigrationWindow {
+                    start,
+                    end: start.plus_seconds(100),
+                    grace_period: Some(50),
+                }),
+            },
+        )
+        .unwrap();
+        assert_eq!(window_status(&response).as_deref(), Some("not_started"));
+
+        let depositor = message_info(&deps.api.addr_make("user"), &[coin(100, "pg")]);
+        let deposit = ExecuteMsg::Deposit {
+            chain_id: "1".to_string(),
+        };
+        let not_open =
+            |result: Result<Response<PalomaMsg>, ContractError>, expected: &str| match result
+                .unwrap_err()
+            {
+                ContractError::WindowNotOpen { status, .. } => assert_eq!(status, expected),
+                err => panic!("unexpected error: {err}"),
+            };
+        not_open(
+            execute(
+                deps.as_mut(),
+                env_at(0),
+                info.clone(),
+                release("1", Uint256::one(), 1),
+            ),
+            "not_started",
+        );
+        not_open(
+            execute(deps.as_mut(), env_at(0), depositor.clone(), deposit.clone()),
+            "not_started",
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            env_at(150),
+            depositor.clone(),
+            deposit.clone(),
+        )
+        .unwrap();
+        assert_eq!(window_status(&response).as_deref(), Some("open"));
+        let response = execute(
+            deps.as_mut(),
+            env_at(150),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap();
+        assert_eq!(window_status(&response), None);
+
+        // Releases are still accepted during the grace period, deposits are not.
+        let response = execute(
+            deps.as_mut(),
+            env_at(220),
+            info.clone(),
+            release("1", Uint256::one(), 2),
+        )
+        .unwrap();
+        assert_eq!(window_status(&response).as_deref(), Some("grace"));
+        not_open(
+            execute(deps.as_mut(), env_at(220), depositor, deposit),
+            "grace",
+        );
+        not_open(
+            execute(
+                deps.as_mut(),
+                env_at(300),
+                info,
+                release("1", Uint256::one(), 3),
+            ),
+            "closed",
+        );
+    }
+
+    #[test]
+    fn deposit_requires_a_registered_chain() {
+        let (mut deps, _) = setup();
+        let depositor = message_info(&deps.api.addr_make("user"), &[coin(100, "pg")]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            depositor,
+            ExecuteMsg::Deposit {
+                chain_id: "9".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownChain { .. }));
+    }
+
+    fn create_vesting(deps: &mut TestDeps, info: &MessageInfo) {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CreateVesting {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+                start: mock_env().block.time,
+                cliff: 100,
+                duration: 1000,
+                total: Uint256::from(1000u16),
+            },
+        )
+        .unwrap();
+    }
+
+    fn release_vested(deps: &mut TestDeps, seconds: u64) -> Result<Uint256, ContractError> {
+        let anyone = message_info(&deps.api.addr_make("anyone"), &[]);
+        execute(
+            deps.as_mut(),
+            env_at(seconds),
+            anyone,
+            ExecuteMsg::ReleaseVested {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+            },
+        )?;
+        Ok(VESTING_SCHEDULES
+            .load(&deps.storage, ("1".to_string(), RECIPIENT.to_string()))?
+            .released)
+    }
+
+    fn contract_nonce(counter: u64) -> Uint256 {
+        (Uint256::one() << 255) + Uint256::from(counter)
+    }
+
+    #[test]
+    fn release_vested_follows_cliff_and_linear_schedule() {
+        let (mut deps, info) = setup();
+        create_vesting(&mut deps, &info);
+
+        let err = release_vested(&mut deps, 99).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToRelease {}));
+        assert_eq!(
+            release_vested(&mut deps, 100).unwrap(),
+            Uint256::from(100u16)
+        );
+        assert_eq!(
+            release_vested(&mut deps, 550).unwrap(),
+            Uint256::from(550u16)
+        );
+        let err = release_vested(&mut deps, 550).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToRelease {}));
+        assert_eq!(
+            release_vested(&mut deps, 5000).unwrap(),
+            Uint256::from(1000u16)
+        );
+
+        let record = releases()
+            .load(
+                &deps.storage,
+                ("1".to_string(), contract_nonce(1).to_string()),
+            )
+            .unwrap();
+        assert_eq!(record.amount, Uint256::from(450u16));
+        assert!(record.vested);
+    }
+
+    #[test]
+    fn contract_nonces_are_reserved_for_vested_releases() {
+        let (mut deps, info) = setup();
+        let reserved = |result: Result<Response<PalomaMsg>, ContractError>| {
+            assert!(matches!(
+                result.unwrap_err(),
+                ContractError::ReservedNonce { .. }
+            ))
+        };
+        reserved(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Release {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: Uint256::one(),
+                nonce: contract_nonce(0),
+                token: None,
+            },
+        ));
+        let mut entry = item(1);
+        entry.nonce = contract_nonce(0);
+        reserved(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![entry]),
+        ));
+        reserved(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::BackfillReleases {
+                releases: vec![BackfillItem {
+                    chain_id: "1".to_string(),
+                    nonce: contract_nonce(0),
+                    recipient: RECIPIENT.to_string(),
+                    amount: Uint256::one(),
+                }],
+            },
+        ));
+        let mut record = ReleaseRecord::legacy("1".to_string(), mock_env().block.time);
+        record.status = ReleaseStatus::Scheduled;
+        reserved(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::ImportState {
+                records: vec![StateRecord::Release {
+                    nonce: contract_nonce(0).to_string(),
+                    record,
+                }],
+            },
+        ));
+
+        // Vesting still gets the first contract nonce.
+        create_vesting(&mut deps, &info);
+        release_vested(&mut deps, 100).unwrap();
+        assert!(
+            releases()
+                .load(
+                    &deps.storage,
+                    ("1".to_string(), contract_nonce(0).to_string())
+                )
+                .unwrap()
+                .vested
+        );
+    }
+
+    #[test]
+    fn cancel_returns_only_vested_releases_to_the_schedule() {
+        let (mut deps, info) = setup();
+        create_vesting(&mut deps, &info);
+        release_vested(&mut deps, 500).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(7u8), 1),
+        )
+        .unwrap();
+
+        let vesting_key = ("1".to_string(), RECIPIENT.to_string());
+        let cancel = |nonce| ExecuteMsg::CancelRelease {
+            chain_id: "1".to_string(),
+            nonce,
+            forward: false,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            cancel(Uint256::one()),
+        )
+        .unwrap();
+        let schedule = VESTING_SCHEDULES
+            .load(&deps.storage, vesting_key.clone())
+            .unwrap();
+        assert_eq!(schedule.released, Uint256::from(500u16));
+        execute(deps.as_mut(), mock_env(), info, cancel(contract_nonce(0))).unwrap();
+        let schedule = VESTING_SCHEDULES.load(&deps.storage, vesting_key).unwrap();
+        assert_eq!(schedule.released, Uint256::zero());
+        assert_eq!(
+            release_vested(&mut deps, 500).unwrap(),
+            Uint256::from(500u16)
+        );
+    }
+
+    fn recipient_totals(deps: &TestDeps) -> RecipientTotalsResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RecipientTotals {
+                    chain_id: "1".to_string(),
+                    recipient: RECIPIENT.to_string(),
+                    token: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn recipient_cap_is_freed_by_cancel() {
+        let (mut deps, info) = setup();
+        register_chain(
+            &mut deps,
+            &info,
+            "1",
+            r#"{"job_id":"j","recipient_cap":"12"}"#,
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(10u8), 1),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(5u8), 2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RecipientCapExceeded { .. }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CancelRelease {
+                chain_id: "1".to_string(),
+                nonce: Uint256::one(),
+                forward: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(recipient_totals(&deps).released, Uint256::zero());
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            release("1", Uint256::from(5u8), 3),
+        )
+        .unwrap();
+        let totals = recipient_totals(&deps);
+        assert_eq!(totals.released, Uint256::from(5u8));
+        assert_eq!(totals.cap, Some(Uint256::from(12u8)));
+    }
+
+    #[test]
+    fn recipient_totals_count_retries_once() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1), item(2)]),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env_at(100),
+            info.clone(),
+            batch("1", vec![item(2)]),
+        )
+        .unwrap();
+        let mut retry = item(1);
+        retry.amount = Uint256::from(6u8);
+        let err = execute(deps.as_mut(), env_at(100), info, batch("1", vec![retry])).unwrap_err();
+        assert!(matches!(err, ContractError::NonceMismatch {}));
+        assert_eq!(recipient_totals(&deps).released, Uint256::from(10u8));
+    }
+
+    #[test]
+    fn recipient_history_is_in_nonce_string_order() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            batch("1", vec![item(2), item(10), item(3)]),
+        )
+        .unwrap();
+        let history = |start_after: Option<u64>| -> Vec<Uint256> {
+            let response: RecipientHistoryResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::RecipientHistory {
+                        chain_id: "1".to_string(),
+                        recipient: RECIPIENT.to_string(),
+                        start_after: start_after.map(Uint256::from),
+                        limit: Some(2),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response
+                .releases
+                .into_iter()
+                .map(|release| release.nonce)
+                .collect()
+        };
+        assert_eq!(history(None), vec![Uint256::from(10u8), Uint256::from(2u8)]);
+        assert_eq!(history(Some(2)), vec![Uint256::from(3u8)]);
+    }
+
+    #[test]
+    fn supply_allowance_compares_denom_units() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "2", r#"{"job_id":"k"}"#);
+        for msg in [
+            ExecuteMsg::SetConversionRatio {
+                chain_id: "2".to_string(),
+                epoch: 1,
+                conversion_ratio: ConversionRatio {
+                    ratio: Decimal256::one(),
+                    palomagold_decimals: 6,
+                    evm_decimals: 18,
+                },
+            },
+            ExecuteMsg::SendPalomaGold {
+                chain_id: "2".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: Uint128::new(1_000_000),
+                token: None,
+            },
+            ExecuteMsg::SetSupplyAllowance {
+                token: None,
+                allowance: Some(Uint256::zero()),
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+
+        // One whole token on an 18 decimal chain is backed by one whole 6 decimal palomagold.
+        let one = Uint256::from(1_000_000_000_000_000_000u128);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("2", one, 1),
+        )
+        .unwrap();
+        let supply = SUPPLY_TOTALS.load(&deps.storage, "pg".to_string()).unwrap();
+        assert_eq!(supply.released, Uint256::from(1_000_000u32));
+        let record = releases()
+            .load(&deps.storage, ("2".to_string(), "1".to_string()))
+            .unwrap();
+        assert_eq!(record.denom_amount, Some(Uint256::from(1_000_000u32)));
+
+        // Dust below one denom base unit is rounded up.
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("2", Uint256::one(), 2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SupplyExceeded { .. }));
+
+        let cancel_first = ExecuteMsg::CancelRelease {
+            chain_id: "2".to_string(),
+            nonce: Uint256::one(),
+            forward: false,
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), cancel_first).unwrap();
+        let supply = SUPPLY_TOTALS.load(&deps.storage, "pg".to_string()).unwrap();
+        assert_eq!(supply.released, Uint256::zero());
+        execute(deps.as_mut(), mock_env(), info, release("2", one, 2)).unwrap();
+    }
+
+    #[test]
+    fn deposits_and_bridges_count_towards_the_supply() {
+        let (mut deps, info) = setup();
+        let depositor = message_info(&deps.api.addr_make("user"), &[coin(100, "pg")]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            depositor,
+            ExecuteMsg::Deposit {
+                chain_id: "1".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetSupplyAllowance {
+                token: None,
+                allowance: Some(Uint256::from(2u8)),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(3u8), 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SupplyExceeded { .. }));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SendPalomaGold {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: Uint128::new(1),
+                token: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            release("1", Uint256::from(3u8), 2),
+        )
+        .unwrap();
+        let supply = SUPPLY_TOTALS.load(&deps.storage, "pg".to_string()).unwrap();
+        assert_eq!(supply.received, Uint128::new(100));
+        assert_eq!(supply.bridged, Uint128::new(1));
+        assert_eq!(supply.released, Uint256::from(3u8));
+    }
+
+    #[test]
+    fn denylist_blocks_recipients_and_depositors() {
+        let (mut deps, info) = setup();
+        let depositor = deps.api.addr_make("user");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddToDenylist {
+                evm_addresses: vec![RECIPIENT.to_string()],
+                paloma_addresses: vec![depositor.to_string()],
+            },
+        )
+        .unwrap();
+        let denied = |result: Result<Response<PalomaMsg>, ContractError>| {
+            assert!(matches!(result.unwrap_err(), ContractError::Denied { .. }))
+        };
+        denied(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        ));
+        denied(execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SendPalomaGold {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: Uint128::one(),
+                token: None,
+            },
+        ));
+        denied(execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&depositor, &[coin(100, "pg")]),
+            ExecuteMsg::Deposit {
+                chain_id: "1".to_string(),
+            },
+        ));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::RemoveFromDenylist {
+                evm_addresses: vec![RECIPIENT.to_string()],
+                paloma_addresses: vec![],
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn denylist_is_paged() {
+        let (mut deps, info) = setup();
+        let addresses: Vec<String> = (1..=3u8)
+            .map(|n| format!("0x{}", format!("{n:02x}").repeat(20)))
+            .collect();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AddToDenylist {
+                evm_addresses: addresses.clone(),
+                paloma_addresses: vec![],
+            },
+        )
+        .unwrap();
+        let page = |start_after: Option<&String>| -> Vec<String> {
+            let response: DenylistResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    mock_env(),
+                    QueryMsg::Denylist {
+                        kind: DenylistKind::Evm,
+                        start_after: start_after.cloned(),
+                        limit: Some(2),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            response.addresses
+        };
+        assert_eq!(page(None), addresses[..2]);
+        assert_eq!(page(Some(&addresses[1])), addresses[2..]);
+    }
+
+    fn cancel(nonce: u64) -> ExecuteMsg {
+        ExecuteMsg::CancelRelease {
+            chain_id: "1".to_string(),
+            nonce: Uint256::from(nonce),
+            forward: false,
+        }
+    }
+
+    #[test]
+    fn releases_expire_after_their_ttl() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "1", r#"{"job_id":"j","release_ttl":300}"#);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env_at(299),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(400),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReleaseExpired {}));
+        // Expired releases can still be cancelled to free their capacity.
+        execute(deps.as_mut(), env_at(400), info, cancel(1)).unwrap();
+    }
+
+    #[test]
+    fn cancelled_releases_cannot_be_retried() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), cancel(1)).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(100),
+            info.clone(),
+            release("1", Uint256::one(), 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ReleaseAbandoned {}));
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), cancel(1)).unwrap_err();
+        assert!(matches!(err, ContractError::ReleaseAbandoned {}));
+        let err = execute(deps.as_mut(), mock_env(), info, cancel(2)).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownRelease {}));
+    }
+
+    #[test]
+    fn cancel_reports_totals_underflow() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(10u8), 1),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::ImportState {
+                records: vec![StateRecord::SupplyTotals {
+                    denom: "pg".to_string(),
+                    totals: SupplyTotals::default(),
+                }],
+            },
+        )
+        .unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), info, cancel(1)).unwrap_err();
+        assert!(matches!(err, ContractError::TotalsUnderflow { .. }));
+    }
+
+    fn bank_sends(response: &Response<PalomaMsg>) -> Vec<(String, Vec<Coin>)> {
+        response
+            .messages
+            .iter()
+            .filter_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    Some((to_address.clone(), amount.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn emergency_withdrawal_is_timelocked() {
+        let (mut deps, info) = setup();
+        let to = deps.api.addr_make("safe").to_string();
+        let schedule = ExecuteMsg::EmergencyWithdraw {
+            denom: "pg".to_string(),
+            amount: Uint128::new(40),
+            to: to.clone(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), schedule.clone()).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(2 * 24 * 60 * 60 - 1),
+            info.clone(),
+            ExecuteMsg::ExecuteEmergencyWithdraw {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Timelocked { .. }));
+
+        let response = execute(
+            deps.as_mut(),
+            env_at(2 * 24 * 60 * 60),
+            info.clone(),
+            ExecuteMsg::ExecuteEmergencyWithdraw {},
+        )
+        .unwrap();
+        assert_eq!(bank_sends(&response), vec![(to, vec![coin(40, "pg")])]);
+        let err = execute(
+            deps.as_mut(),
+            env_at(2 * 24 * 60 * 60),
+            info.clone(),
+            ExecuteMsg::ExecuteEmergencyWithdraw {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingWithdrawal {}));
+
+        execute(deps.as_mut(), mock_env(), info.clone(), schedule).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CancelEmergencyWithdraw {},
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env_at(3 * 24 * 60 * 60),
+            info,
+            ExecuteMsg::ExecuteEmergencyWithdraw {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingWithdrawal {}));
+    }
+
+    #[test]
+    fn sweep_sends_the_whole_stray_balance() {
+        let (mut deps, info) = setup();
+        let to = deps.api.addr_make("safe").to_string();
+        let sweep = |denom: &str| ExecuteMsg::SweepStrayFunds {
+            denom: denom.to_string(),
+            to: to.clone(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), sweep("ustray")).unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        deps.querier
+            .bank
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(50, "ustray"), coin(70, "pg")]);
+        let response = execute(deps.as_mut(), mock_env(), info.clone(), sweep("ustray")).unwrap();
+        assert_eq!(
+            bank_sends(&response),
+            vec![(to.clone(), vec![coin(50, "ustray")])]
+        );
+        let err = execute(deps.as_mut(), mock_env(), info, sweep("pg")).unwrap_err();
+        assert!(matches!(err, ContractError::NotStrayDenom { .. }));
+    }
+
+    #[test]
+    fn messages_other_than_deposit_refuse_funds() {
+        let (mut deps, _) = setup();
+        let messages = [
+            r#"{"register_chain":{"chain_id":"1","chain_setting":{"job_id":"j"}}}"#,
+            r#"{"send_paloma_gold":{"chain_id":"1","recipient":"","amount":"1"}}"#,
+            r#"{"release":{"chain_id":"1","recipient":"","amount":"1","nonce":"1"}}"#,
+            r#"{"register_token":{"token_key":"x","token":{"denom":"ux","chains":{}}}}"#,
+            r#"{"add_to_denylist":{}}"#,
+            r#"{"remove_from_denylist":{}}"#,
+            r#"{"announce_job_rotation":{"chain_id":"1","new_job_id":"k","overlap":0}}"#,
+            r#"{"finalize_job_rotation":{"chain_id":"1"}}"#,
+            r#"{"cancel_job_rotation":{"chain_id":"1"}}"#,
+            r#"{"add_hook":{"event":"release_scheduled","contract":""}}"#,
+            r#"{"remove_hook":{"event":"release_scheduled","contract":""}}"#,
+            r#"{"set_supply_allowance":{}}"#,
+            r#"{"set_conversion_ratio":{"chain_id":"1","epoch":1,"conversion_ratio":{"ratio":"1","palomagold_decimals":6,"evm_decimals":18}}}"#,
+            r#"{"release_from_aave":{"chain_id":"1","recipient":"","aave_amount":"1","nonce":"1"}}"#,
+            r#"{"batch_release":{"chain_id":"1","releases":[]}}"#,
+            r#"{"cancel_tx":{"transaction_id":1}}"#,
+            r#"{"create_vesting":{"chain_id":"1","recipient":"","start":"0","cliff":0,"duration":1,"total":"1"}}"#,
+            r#"{"release_vested":{"chain_id":"1","recipient":""}}"#,
+            r#"{"cancel_release":{"chain_id":"1","nonce":"1","forward":false}}"#,
+            r#"{"confirm_releases":{"chain_id":"1","nonces":[]}}"#,
+            r#"{"settle_skyway_sends":{"amount":"1"}}"#,
+            r#"{"convert_legacy_releases":{}}"#,
+            r#"{"backfill_releases":{"releases":[]}}"#,
+            r#"{"import_state":{"records":[]}}"#,
+            r#"{"seal":{}}"#,
+            r#"{"emergency_withdraw":{"denom":"pg","amount":"1","to":""}}"#,
+            r#"{"execute_emergency_withdraw":{}}"#,
+            r#"{"cancel_emergency_withdraw":{}}"#,
+            r#"{"sweep_stray_funds":{"denom":"ustray","to":""}}"#,
+            r#"{"set_migration_window":{"chain_id":"1"}}"#,
+            r#"{"sync_migration_window":{"chain_id":"1"}}"#,
+            r#"{"set_paloma":{"chain_id":"1"}}"#,
+            r#"{"update_refund_wallet":{"chain_id":"1","new_refund_wallet":""}}"#,
+            r#"{"update_gas_fee":{"chain_id":"1","new_gas_fee":"1"}}"#,
+            r#"{"update_service_fee_collector":{"chain_id":"1","new_service_fee_collector":""}}"#,
+            r#"{"update_service_fee":{"chain_id":"1","new_service_fee":"1"}}"#,
+            r#"{"broadcast_service_fee_collector":{"chains":"all","new_service_fee_collector":""}}"#,
+            r#"{"broadcast_service_fee":{"chains":"all","new_service_fee":"1"}}"#,
+            r#"{"set_fee_bounds":{"chain_id":"1","fee":"gas_fee"}}"#,
+            r#"{"set_fee_override_approver":{}}"#,
+            r#"{"approve_fee_override":{"chain_id":"1","fee":"gas_fee","value":"1"}}"#,
+            r#"{"set_paloma_fee":{"bps":1,"collector":""}}"#,
+            r#"{"claim_fees":{}}"#,
+        ];
+        let info = message_info(&deps.api.addr_make("owner"), &[coin(1, "pg")]);
+        for message in messages {
+            let msg: ExecuteMsg = from_json(message).unwrap();
+            let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+            assert!(
+                matches!(err, ContractError::InvalidFunds { .. }),
+                "{message}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn deposit_takes_a_single_bridged_coin() {
+        let (mut deps, _) = setup();
+        let sender = deps.api.addr_make("user");
+        let deposit = ExecuteMsg::Deposit {
+            chain_id: "1".to_string(),
+        };
+        for funds in [
+            vec![],
+            vec![coin(0, "pg")],
+            vec![coin(1, "ustray")],
+            vec![coin(1, "pg"), coin(1, "ustray")],
+        ] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                message_info(&sender, &funds),
+                deposit.clone(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidFunds { .. }));
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&sender, &[coin(1, "pg")]),
+            deposit,
+        )
+        .unwrap();
+    }
+
+    fn register_token(
+        deps: &mut TestDeps,
+        info: &MessageInfo,
+        token: &str,
+    ) -> Result<(), ContractError> {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::RegisterToken {
+                token_key: "x".to_string(),
+                token: from_json(token).unwrap(),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn release_token(chain_id: &str, token: &str, amount: Uint256, nonce: u64) -> ExecuteMsg {
+        ExecuteMsg::Release {
+            chain_id: chain_id.to_string(),
+            recipient: RECIPIENT.to_string(),
+            amount,
+            nonce: Uint256::from(nonce),
+            token: Some(token.to_string()),
+        }
+    }
+
+    #[test]
+    fn registered_tokens_are_released_and_counted_in_their_denom() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "2", r#"{"job_id":"k"}"#);
+        register_token(
+            &mut deps,
+            &info,
+            &format!(r#"{{"denom":"ux","decimals":6,"chains":{{"1":{{"address":"{RECIPIENT}","decimals":18}}}}}}"#),
+        )
+        .unwrap();
+
+        let one = Uint256::from(1_000_000_000_000_000_000u128);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release_token("1", "x", one, 1),
+        )
+        .unwrap();
+        let supply = SUPPLY_TOTALS.load(&deps.storage, "ux".to_string()).unwrap();
+        assert_eq!(supply.released, Uint256::from(1_000_000u32));
+        assert!(!SUPPLY_TOTALS.has(&deps.storage, "pg".to_string()));
+
+        for (chain_id, token) in [("2", "x"), ("1", "y")] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                release_token(chain_id, token, one, 2),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::UnknownToken { .. }));
+        }
+        let err = register_token(&mut deps, &info, r#"{"denom":"uy","chains":{}}"#).unwrap_err();
+        assert!(matches!(err, ContractError::TokenDenomChanged {}));
+    }
+
+    #[test]
+    fn sweep_refuses_registered_token_denoms() {
+        let (mut deps, info) = setup();
+        register_token(&mut deps, &info, r#"{"denom":"ux","chains":{}}"#).unwrap();
+        deps.querier
+            .bank
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(50, "ux")]);
+        let to = deps.api.addr_make("safe").to_string();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::SweepStrayFunds {
+                denom: "ux".to_string(),
+                to,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NotStrayDenom { .. }));
+    }
+
+    fn treasury(deps: &TestDeps) -> Vec<TreasuryBalance> {
+        let response: TreasuryResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Treasury {}).unwrap()).unwrap();
+        response.balances
+    }
+
+    #[test]
+    fn treasury_frees_confirmed_releases_and_settled_sends() {
+        let (mut deps, info) = setup();
+        register_token(
+            &mut deps,
+            &info,
+            &format!(r#"{{"denom":"ux","decimals":6,"chains":{{"1":{{"address":"{RECIPIENT}","decimals":18}}}}}}"#),
+        )
+        .unwrap();
+        deps.querier
+            .bank
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(1000, "pg"), coin(1000, "ux")]);
+        for msg in [
+            release("1", Uint256::from(10u8), 1),
+            release_token("1", "x", Uint256::from(2_500_000_000_000u64), 2),
+            ExecuteMsg::SendPalomaGold {
+                chain_id: "1".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: Uint128::new(100),
+                token: None,
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+
+        let balances = treasury(&deps);
+        assert_eq!(balances[0].denom, "pg");
+        assert_eq!(balances[0].committed, Uint128::new(100));
+        assert_eq!(balances[0].reserved, Uint256::from(10u8));
+        assert_eq!(balances[0].free, Uint256::from(990u16));
+        // Token reserves are converted into denom units, rounding up.
+        assert_eq!(balances[1].token.as_deref(), Some("x"));
+        assert_eq!(balances[1].reserved, Uint256::from(3u8));
+        assert_eq!(balances[1].free, Uint256::from(997u16));
+
+        for msg in [
+            ExecuteMsg::ConfirmReleases {
+                chain_id: "1".to_string(),
+                nonces: vec![Uint256::one(), Uint256::from(2u8)],
+            },
+            ExecuteMsg::SettleSkywaySends {
+                token: None,
+                amount: Uint128::new(100),
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+        let balances = treasury(&deps);
+        assert_eq!(balances[0].committed, Uint128::zero());
+        assert_eq!(balances[0].free, Uint256::from(1000u16));
+        assert_eq!(balances[1].free, Uint256::from(1000u16));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::SettleSkywaySends {
+                token: None,
+                amount: Uint128::one(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SettleExceedsCommitted { .. }));
+    }
+
+    fn set_version(deps: &mut TestDeps, contract: &str, version: &str) {
+        set_contract_version(&mut deps.storage, contract, version).unwrap();
+    }
+
+    #[test]
+    fn migrate_upgrades_chain_settings_and_version() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "2", r#"{"job_id":"j2","release_ttl":30}"#);
+        set_version(&mut deps, CONTRACT_NAME, "0.1.1");
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(),
+            from_json(r#"{"release_ttl":60}"#).unwrap(),
+        )
+        .unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "migrated"));
+
+        let chain_setting = CHAIN_SETTINGS.load(&deps.storage, "1".to_string()).unwrap();
+        assert_eq!(chain_setting.release_ttl, Some(60));
+        let chain_setting = CHAIN_SETTINGS.load(&deps.storage, "2".to_string()).unwrap();
+        assert_eq!(chain_setting.release_ttl, Some(30));
+        assert_eq!(
+            cw2::get_contract_version(&deps.storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+    }
+
+    #[test]
+    fn migrate_refuses_other_contracts_and_downgrades() {
+        let (mut deps, _) = setup();
+        set_version(&mut deps, CONTRACT_NAME, "99.0.0");
+        let err = migrate(deps.as_mut(), mock_env(), from_json("{}").unwrap()).unwrap_err();
+        assert!(matches!(err, ContractError::CannotDowngrade { .. }));
+
+        set_version(&mut deps, "crates.io:other", "0.1.0");
+        let err = migrate(deps.as_mut(), mock_env(), from_json("{}").unwrap()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidContractName { .. }));
+    }
+
+    /// Stores `count` 0.1 withdraw timestamps and migrates to the current version.
+    fn migrate_legacy(deps: &mut TestDeps, count: u64, msg: &str) {
+        for nonce in 1..=count {
+            WITHDRAW_TIMESTAMP
+                .save(
+                    &mut deps.storage,
+                    ("1".to_string(), nonce.to_string()),
+                    &Timestamp::from_seconds(1),
+                )
+                .unwrap();
+        }
+        set_version(deps, CONTRACT_NAME, "0.1.1");
+        migrate(deps.as_mut(), mock_env(), from_json(msg).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn legacy_releases_are_converted_in_batches() {
+        let (mut deps, info) = setup();
+        migrate_legacy(&mut deps, 3, r#"{"legacy_batch":2}"#);
+        let converted = |deps: &TestDeps, nonce: &str| {
+            releases()
+                .may_load(&deps.storage, ("1".to_string(), nonce.to_string()))
+                .unwrap()
+                .is_some()
+        };
+        assert!(converted(&deps, "2"));
+        assert!(!converted(&deps, "3"));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::ConvertLegacyReleases { limit: None },
+        )
+        .unwrap();
+        let record = releases()
+            .load(&deps.storage, ("1".to_string(), "3".to_string()))
+            .unwrap();
+        assert_eq!(record.status, ReleaseStatus::Legacy);
+        assert!(record.recipient.is_empty());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::ConvertLegacyReleases { limit: None },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoLegacyConversion {}));
+    }
+
+    #[test]
+    fn legacy_releases_are_retried_only_once_backfilled() {
+        let (mut deps, info) = setup();
+        migrate_legacy(&mut deps, 2, "{}");
+
+        // Nothing is known about the release, so it can be neither cancelled nor retried.
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), cancel(1)).unwrap_err();
+        assert!(matches!(err, ContractError::LegacyRelease {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(5u8), 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LegacyRelease {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(2)]),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LegacyRelease {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::BackfillReleases {
+                releases: vec![BackfillItem {
+                    chain_id: "1".to_string(),
+                    nonce: Uint256::from(2u8),
+                    recipient: RECIPIENT.to_string(),
+                    amount: Uint256::from(5u8),
+                }],
+            },
+        )
+        .unwrap();
+        assert_eq!(recipient_totals(&deps).released, Uint256::from(5u8));
+
+        // A backfilled release is retried like any other and not counted again.
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(7u8), 2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NonceMismatch {}));
+        let response = execute(deps.as_mut(), mock_env(), info, batch("1", vec![item(2)])).unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string()]);
+        assert_eq!(recipient_totals(&deps).released, Uint256::from(5u8));
+        // Legacy releases predate supply accounting and are not added to it.
+        let supply = SUPPLY_TOTALS
+            .may_load(&deps.storage, "pg".to_string())
+            .unwrap()
+            .unwrap_or_default();
+        assert_eq!(supply.released, Uint256::zero());
+    }
+
+    fn export_all(deps: &TestDeps) -> Vec<StateRecord> {
+        let namespaces: Vec<StateNamespace> = from_json(
+            r#"["state","chain_settings","withdraw_timestamps","conversion_ratios",
+            "current_epochs","migration_windows","window_statuses","vesting_schedules",
+            "contract_nonces","recipient_totals","releases","supply_totals",
+            "released_per_chain","supply_allowances","denied_evm_addresses",
+            "denied_paloma_addresses","pending_emergency_withdrawal","tokens",
+            "reserved_releases","skyway_committed","legacy_conversion","hooks",
+            "job_rotations","fee_bounds","forwarded_fees","fee_overrides",
+            "fee_override_approver","paloma_fee","accrued_fees","fee_paid_deposits"]"#,
+        )
+        .unwrap();
+        let mut records = vec![];
+        for namespace in namespaces {
+            let mut start_after = None;
+            loop {
+                let page: ExportStateResponse = from_json(
+                    query(
+                        deps.as_ref(),
+                        mock_env(),
+                        QueryMsg::ExportState {
+                            namespace: namespace.clone(),
+                            start_after,
+                            limit: Some(2),
+                        },
+                    )
+                    .unwrap(),
+                )
+                .unwrap();
+                records.extend(page.records);
+                match page.next {
+                    Some(next) => start_after = Some(next),
+                    None => break,
+                }
+            }
+        }
+        records
+    }
+
+    fn recipient_history(deps: &TestDeps) -> RecipientHistoryResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::RecipientHistory {
+                    chain_id: "1".to_string(),
+                    recipient: RECIPIENT.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn exported_state_imports_into_a_fresh_contract() {
+        let (mut deps, info) = setup();
+        create_vesting(&mut deps, &info);
+        release_vested(&mut deps, 500).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1), item(2), item(3)]),
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), cancel(2)).unwrap();
+        let records = export_all(&deps);
+
+        let (mut imported, info) = setup();
+        for chunk in records.chunks(5) {
+            execute(
+                imported.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::ImportState {
+                    records: chunk.to_vec(),
+                },
+            )
+            .unwrap();
+        }
+        assert_eq!(export_all(&imported), records);
+        // The recipient index of the releases is rebuilt by the import.
+        let history = recipient_history(&imported);
+        assert_eq!(history, recipient_history(&deps));
+        assert_eq!(history.releases.len(), 4);
+
+        execute(
+            imported.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Seal {},
+        )
+        .unwrap();
+        let err = execute(
+            imported.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::ImportState { records },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Sealed {}));
+    }
+
+    fn add_hook(deps: &mut TestDeps, info: &MessageInfo, contract: &Addr) {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AddHook {
+                event: HookEvent::ReleaseScheduled,
+                contract: contract.to_string(),
+            },
+        )
+        .unwrap();
+    }
+
+    fn hook_deliveries(response: &Response<PalomaMsg>) -> Vec<(String, HookMsg)> {
+        response
+            .messages
+            .iter()
+            .filter_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) => Some((contract_addr.clone(), from_json(msg).unwrap())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hooks_are_delivered_once_per_message() {
+        let (mut deps, info) = setup();
+        let first = deps.api.addr_make("first");
+        let second = deps.api.addr_make("second");
+        add_hook(&mut deps, &info, &first);
+        add_hook(&mut deps, &info, &second);
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(5u8), 1),
+        )
+        .unwrap();
+        let deliveries = hook_deliveries(&response);
+        assert_eq!(deliveries.len(), 2);
+        assert!(matches!(
+            &deliveries[0].1,
+            HookMsg::MigratorHook {
+                event: ContractEvent::ReleaseScheduled { .. }
+            }
+        ));
+
+        // A batch reaches each subscriber once, with every release it scheduled.
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            batch("1", vec![item(2), item(3), item(4)]),
+        )
+        .unwrap();
+        let deliveries = hook_deliveries(&response);
+        let mut contracts: Vec<&str> = deliveries.iter().map(|(c, _)| c.as_str()).collect();
+        contracts.sort();
+        let mut expected = vec![first.as_str(), second.as_str()];
+        expected.sort();
+        assert_eq!(contracts, expected);
+        for (_, msg) in deliveries {
+            match msg {
+                HookMsg::MigratorHookBatch { events } => assert_eq!(events.len(), 3),
+                msg => panic!("unexpected hook message {msg:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn failed_hook_deliveries_are_reported_by_reply() {
+        let (mut deps, info) = setup();
+        let subscriber = deps.api.addr_make("subscriber");
+        add_hook(&mut deps, &info, &subscriber);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            release("1", Uint256::from(5u8), 1),
+        )
+        .unwrap();
+        let delivery = response
+            .messages
+            .iter()
+            .find(|sub_msg| matches!(sub_msg.msg, CosmosMsg::Wasm(_)))
+            .unwrap();
+        assert_eq!(delivery.reply_on, ReplyOn::Error);
+
+        #[allow(deprecated)]
+        let failure = Reply {
+            id: delivery.id,
+            payload: delivery.payload.clone(),
+            gas_used: 0,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        let response = reply(deps.as_mut(), mock_env(), failure.clone()).unwrap();
+        let event = &response.events[0];
+        assert_eq!(event.ty, "hook_failed");
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "contract" && attr.value == subscriber.as_str()));
+        assert!(event
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "error" && attr.value == "out of gas"));
+
+        let err = reply(deps.as_mut(), mock_env(), Reply { id: 7, ..failure }).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownReplyId { id: 7 }));
+    }
+
+    #[test]
+    fn register_chain_creates_its_job() {
+        let (mut deps, info) = setup();
+        let create = |abi: &str| ExecuteMsg::RegisterChain {
+            chain_id: "2".to_string(),
+            chain_setting: from_json(r#"{"job_id":"j2"}"#).unwrap(),
+            create_job: Some(NewJob {
+                address: RECIPIENT.to_string(),
+                abi: abi.to_string(),
+                chain_type: None,
+                is_mev: false,
+            }),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), create("{")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidJob { .. }));
+
+        let response = execute(deps.as_mut(), mock_env(), info, create("[]")).unwrap();
+        let created: Vec<&CreateJob> = response
+            .messages
+            .iter()
+            .filter_map(|sub_msg| match &sub_msg.msg {
+                CosmosMsg::Custom(PalomaMsg::SchedulerMsg {
+                    create_job: Some(create_job),
+                    execute_job: None,
+                }) => Some(create_job),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].job_id, "j2");
+        assert_eq!(created[0].chain_type, "evm");
+        assert_eq!(created[0].chain_reference_id, "2");
+        assert!(created[0].payload_modifiable);
+    }
+
+    #[test]
+    fn scheduler_msg_leaves_out_unset_fields() {
+        let msg = PalomaMsg::SchedulerMsg {
+            create_job: None,
+            execute_job: Some(ExecuteJob {
+                job_id: "j".to_string(),
+                payload: Binary::new(vec![1]),
+            }),
+        };
+        let json = to_json_string(&msg).unwrap();
+        assert_eq!(
+            json,
+            r#"{"scheduler_msg":{"execute_job":{"job_id":"j","payload":"AQ=="}}}"#
+        );
+        assert_eq!(from_json::<PalomaMsg>(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn purpose_jobs_override_the_chain_job() {
+        let (mut deps, info) = setup();
+        register_chain(
+            &mut deps,
+            &info,
+            "1",
+            r#"{"job_id":"j","jobs":{"fee":"f","release":"r"}}"#,
+        );
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::UpdateGasFee {
+                chain_id: "1".to_string(),
+                new_gas_fee: Uint256::from(7u8),
+            },
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["f".to_string()]);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetPaloma {
+                chain_id: "1".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string()]);
+        let response = execute(deps.as_mut(), mock_env(), info, batch("1", vec![item(1)])).unwrap();
+        assert_eq!(jobs(&response), vec!["r".to_string()]);
+    }
+
+    fn announce_rotation(deps: &mut TestDeps, info: &MessageInfo) {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AnnounceJobRotation {
+                chain_id: "1".to_string(),
+                new_job_id: "j2".to_string(),
+                overlap: 1000,
+                create_job: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn job_rotation_keeps_retries_on_the_old_job() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            batch("1", vec![item(1)]),
+        )
+        .unwrap();
+        announce_rotation(&mut deps, &info);
+
+        let response = execute(
+            deps.as_mut(),
+            env_at(100),
+            info.clone(),
+            batch("1", vec![item(1), item(2)]),
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string(), "j2".to_string()]);
+
+        let finalize = ExecuteMsg::FinalizeJobRotation {
+            chain_id: "1".to_string(),
+        };
+        let err = execute(deps.as_mut(), env_at(100), info.clone(), finalize.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Timelocked { .. }));
+        execute(deps.as_mut(), env_at(1100), info.clone(), finalize).unwrap();
+        let response = execute(
+            deps.as_mut(),
+            env_at(1100),
+            info,
+            batch("1", vec![item(1), item(2)]),
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["j2".to_string()]);
+    }
+
+    #[test]
+    fn cancel_is_forwarded_through_the_job_of_the_release() {
+        let (mut deps, info) = setup();
+        register_chain(
+            &mut deps,
+            &info,
+            "1",
+            r#"{"job_id":"j","jobs":{"release":"r"}}"#,
+        );
+        let forward = |nonce: u64| ExecuteMsg::CancelRelease {
+            chain_id: "1".to_string(),
+            nonce: Uint256::from(nonce),
+            forward: true,
+        };
+        for nonce in 1..=3 {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                release("1", Uint256::from(1u8), nonce),
+            )
+            .unwrap();
+        }
+        // A release recorded without its job went through the default job.
+        let release_key = ("1".to_string(), "3".to_string());
+        let mut record = releases().load(&deps.storage, release_key.clone()).unwrap();
+        record.job_id = None;
+        releases()
+            .save(&mut deps.storage, release_key, &record)
+            .unwrap();
+        let response = execute(deps.as_mut(), mock_env(), info.clone(), forward(3)).unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string()]);
+
+        // Releases scheduled during a rotation are cancelled through the new job.
+        announce_rotation(&mut deps, &info);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            release("1", Uint256::from(1u8), 4),
+        )
+        .unwrap();
+
+        let response = execute(deps.as_mut(), mock_env(), info.clone(), forward(4)).unwrap();
+        assert_eq!(jobs(&response), vec!["j2".to_string()]);
+        let response = execute(deps.as_mut(), mock_env(), info.clone(), forward(1)).unwrap();
+        assert_eq!(jobs(&response), vec!["r".to_string()]);
+        let response = execute(deps.as_mut(), mock_env(), info, cancel(2)).unwrap();
+        assert!(jobs(&response).is_empty());
+    }
+
+    #[test]
+    fn broadcasts_reach_every_targeted_chain_once() {
+        let (mut deps, info) = setup();
+        register_chain(
+            &mut deps,
+            &info,
+            "2",
+            r#"{"job_id":"k","jobs":{"fee":"f"}}"#,
+        );
+        let service_fee = |chains: &str| ExecuteMsg::BroadcastServiceFee {
+            chains: from_json(chains).unwrap(),
+            new_service_fee: Uint256::from(5u8),
+        };
+
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            service_fee(r#""all""#),
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["j".to_string(), "f".to_string()]);
+        let response = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::BroadcastServiceFeeCollector {
+                chains: from_json(r#"{"chains":["2","2"]}"#).unwrap(),
+                new_service_fee_collector: RECIPIENT.to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(jobs(&response), vec!["f".to_string()]);
+
+        // An unknown chain fails the whole broadcast rather than skipping it.
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            service_fee(r#"{"chains":["1","9"]}"#),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownChain { .. }));
+        assert_eq!(err.to_string(), "Unknown chain 9");
+    }
+
+    fn update_gas_fee(
+        deps: &mut TestDeps,
+        info: &MessageInfo,
+        fee: u32,
+    ) -> Result<Response<PalomaMsg>, ContractError> {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::UpdateGasFee {
+                chain_id: "1".to_string(),
+                new_gas_fee: Uint256::from(fee),
+            },
+        )
+    }
+
+    #[test]
+    fn fee_updates_stay_within_bounds_unless_approved() {
+        let (mut deps, info) = setup();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetFeeBounds {
+                chain_id: "1".to_string(),
+                fee: FeeKind::GasFee,
+                bounds: from_json(r#"{"min":"10","max":"1000","max_change":"0.5"}"#).unwrap(),
+            },
+        )
+        .unwrap();
+
+        let err = update_gas_fee(&mut deps, &info, 5).unwrap_err();
+        assert!(matches!(err, ContractError::FeeOutOfBounds { .. }));
+        let err = update_gas_fee(&mut deps, &info, 1001).unwrap_err();
+        assert!(matches!(err, ContractError::FeeOutOfBounds { .. }));
+        update_gas_fee(&mut deps, &info, 100).unwrap();
+        // At most half of the last forwarded value may be added or taken away.
+        let err = update_gas_fee(&mut deps, &info, 151).unwrap_err();
+        assert!(matches!(err, ContractError::FeeChangeTooLarge { .. }));
+        update_gas_fee(&mut deps, &info, 150).unwrap();
+        update_gas_fee(&mut deps, &info, 75).unwrap();
+
+        let approver = deps.api.addr_make("approver");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetFeeOverrideApprover {
+                approver: Some(approver.to_string()),
+            },
+        )
+        .unwrap();
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.fee_override_approver, Some(approver.clone()));
+
+        let approve = ExecuteMsg::ApproveFeeOverride {
+            chain_id: "1".to_string(),
+            fee: FeeKind::GasFee,
+            value: Uint256::from(5000u32),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), approve.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&approver, &[]),
+            approve,
+        )
+        .unwrap();
+        // The approval covers a single update to exactly the approved value.
+        let err = update_gas_fee(&mut deps, &info, 4000).unwrap_err();
+        assert!(matches!(err, ContractError::FeeOutOfBounds { .. }));
+        update_gas_fee(&mut deps, &info, 5000).unwrap();
+        let err = update_gas_fee(&mut deps, &info, 5000).unwrap_err();
+        assert!(matches!(err, ContractError::FeeOutOfBounds { .. }));
+    }
+
+    fn bridged(response: &Response<PalomaMsg>) -> String {
+        match &response.messages[0].msg {
+            CosmosMsg::Custom(PalomaMsg::SkywayMsg {
+                send_tx: Some(send_tx),
+                ..
+            }) => send_tx.amount.clone(),
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[test]
+    fn paloma_fee_is_charged_once_per_deposit() {
+        let (mut deps, info) = setup();
+        let collector = deps.api.addr_make("collector");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::SetPalomaFee {
+                bps: 100,
+                collector: collector.to_string(),
+            },
+        )
+        .unwrap();
+        let user = deps.api.addr_make("user");
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&user, &[coin(10_000, "pg")]),
+            ExecuteMsg::Deposit {
+                chain_id: "1".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            execute::accrued_fees(&deps.storage, "pg").unwrap(),
+            Uint128::new(100)
+        );
+        // Half of the balance was sent to the contract without a deposit.
+        deps.querier
+            .bank
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(20_000, "pg")]);
+
+        let send = |amount: u128| ExecuteMsg::SendPalomaGold {
+            chain_id: "1".to_string(),
+            recipient: RECIPIENT.to_string(),
+            amount: Uint128::new(amount),
+            token: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), send(19_901)).unwrap_err();
+        assert!(matches!(err, ContractError::FeesUnclaimed { .. }));
+
+        // The 9,900 left of the deposit are bridged as is; the fee is kept from the rest.
+        let response = execute(deps.as_mut(), mock_env(), info.clone(), send(12_000)).unwrap();
+        assert_eq!(bridged(&response), "11979pg");
+        assert!(response
+            .events
+            .iter()
+            .any(|event| event.ty == "fee_charged"));
+        let response = execute(deps.as_mut(), mock_env(), info, send(1_000)).unwrap();
+        assert_eq!(bridged(&response), "990pg");
+        assert_eq!(
+            execute::accrued_fees(&deps.storage, "pg").unwrap(),
+            Uint128::new(131)
+        );
+    }
+
+    fn quote(deps: &TestDeps, chain_id: &str) -> QuoteResponse {
+        from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Quote {
+                    chain_id: chain_id.to_string(),
+                    amount: Uint128::new(10_000),
+                    token: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn quote_deducts_every_fee() {
+        let (mut deps, info) = setup();
+        register_chain(&mut deps, &info, "2", r#"{"job_id":"k"}"#);
+        // Nothing has been forwarded yet, so only the amount is quoted.
+        let response = quote(&deps, "1");
+        assert_eq!(response.service_fee_rate, None);
+        assert_eq!(response.gas_fee, None);
+        assert_eq!(response.net, Uint256::from(10_000u32));
+
+        let collector = deps.api.addr_make("collector");
+        let one_percent = Uint256::from(10_000_000_000_000_000u128);
+        for msg in [
+            ExecuteMsg::SetPalomaFee {
+                bps: 100,
+                collector: collector.to_string(),
+            },
+            ExecuteMsg::SetConversionRatio {
+                chain_id: "2".to_string(),
+                epoch: 1,
+                conversion_ratio: ratio(Decimal256::one(), 6, 18),
+            },
+        ] {
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+        for (chain_id, gas_fee) in [("1", 7u128), ("2", 1_500_000_000_000)] {
+            for msg in [
+                ExecuteMsg::UpdateGasFee {
+                    chain_id: chain_id.to_string(),
+                    new_gas_fee: Uint256::from(gas_fee),
+                },
+                ExecuteMsg::UpdateServiceFee {
+                    chain_id: chain_id.to_string(),
+                    new_service_fee: one_percent,
+                },
+            ] {
+                execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+            }
+        }
+
+        // 1% is kept on deposit, then 1% of the 9,900 bridged and the gas fee.
+        let response = quote(&deps, "1");
+        assert_eq!(response.paloma_fee, Uint128::new(100));
+        assert_eq!(response.service_fee, Uint256::from(99u8));
+        assert_eq!(response.gas_fee_amount, Uint256::from(7u8));
+        assert_eq!(response.net, Uint256::from(9_794u32));
+        // 1.5 * 10^12 units of an 18 decimal token are 1.5 palomagold units, rounded up.
+        let response = quote(&deps, "2");
+        assert_eq!(response.gas_fee_amount, Uint256::from(2u8));
+        assert_eq!(response.net, Uint256::from(9_799u32));
+    }
+}
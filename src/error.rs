@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal256, StdError, Timestamp, Uint128, Uint256};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,11 +6,168 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Semver parsing error: {0}")]
+    SemVer(#[from] semver::Error),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Pending")]
     Pending {},
+
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("Batch must not be empty")]
+    EmptyBatch {},
+
+    #[error("Epoch {epoch} is older than the current epoch {current_epoch}")]
+    StaleEpoch { epoch: u64, current_epoch: u64 },
+
+    #[error("No conversion ratio set")]
+    NoConversionRatio {},
+
+    #[error("Conversion overflow")]
+    ConversionOverflow {},
+
+    #[error("Migration window must start before it ends")]
+    InvalidWindow {},
+
+    #[error("Migration window of {chain_id} is {status}")]
+    WindowNotOpen { chain_id: String, status: String },
+
+    #[error("Invalid vesting schedule")]
+    InvalidVesting {},
+
+    #[error("Vesting schedule already exists")]
+    VestingExists {},
+
+    #[error("Nothing to release")]
+    NothingToRelease {},
+
+    #[error("Nonce {nonce} is reserved for vested releases")]
+    ReservedNonce { nonce: Uint256 },
+
+    #[error("Nonce was already scheduled with a different recipient or amount")]
+    NonceMismatch {},
+
+    #[error("Recipient cap of {cap} exceeded")]
+    RecipientCapExceeded { cap: Uint256 },
+
+    #[error("Release would exceed the locked supply plus allowance of {limit}")]
+    SupplyExceeded { limit: Uint256 },
+
+    #[error("Invalid funds: {reason}")]
+    InvalidFunds { reason: String },
+
+    #[error("Address {address} is denied")]
+    Denied { address: String },
+
+    #[error("Unknown release")]
+    UnknownRelease {},
+
+    #[error("Release was abandoned")]
+    ReleaseAbandoned {},
+
+    #[error("Release expired")]
+    ReleaseExpired {},
+
+    #[error("Release already completed")]
+    ReleaseCompleted {},
+
+    #[error("Settled amount exceeds the committed amount {committed}")]
+    SettleExceedsCommitted { committed: Uint128 },
+
+    #[error("No pending emergency withdrawal")]
+    NoPendingWithdrawal {},
+
+    #[error("Timelocked until {executable_at}")]
+    Timelocked { executable_at: Timestamp },
+
+    #[error("{denom} is not a stray denom")]
+    NotStrayDenom { denom: String },
+
+    #[error("Unknown token {token}")]
+    UnknownToken { token: String },
+
+    #[error("The denom of a registered token cannot change")]
+    TokenDenomChanged {},
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidContractName { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Legacy releases cannot be cancelled or confirmed, nor retried until backfilled")]
+    LegacyRelease {},
+
+    #[error("Release is not a legacy release")]
+    NotLegacyRelease {},
+
+    #[error("No legacy conversion in progress")]
+    NoLegacyConversion {},
+
+    #[error("Contract is sealed")]
+    Sealed {},
+
+    #[error("Contract is already subscribed to the hook event")]
+    HookExists {},
+
+    #[error("Contract is not subscribed to the hook event")]
+    UnknownHook {},
+
+    #[error("A hook event accepts at most {max} subscribers")]
+    TooManyHooks { max: u32 },
+
+    #[error("Invalid job: {reason}")]
+    InvalidJob { reason: String },
+
+    #[error("A job rotation is already pending")]
+    JobRotationPending {},
+
+    #[error("No job rotation pending")]
+    NoJobRotation {},
+
+    #[error("Job rotation overlap must cover the release TTL of {min} seconds")]
+    RotationTooShort { min: u64 },
+
+    #[error("Unknown chain {chain_id}")]
+    UnknownChain { chain_id: String },
+
+    #[error("Invalid fee bounds: min is above max")]
+    InvalidFeeBounds {},
+
+    #[error("{fee} must be between {min} and {max}")]
+    FeeOutOfBounds {
+        fee: String,
+        min: Uint256,
+        max: Uint256,
+    },
+
+    #[error("{fee} may change by at most {max_change} of its last value")]
+    FeeChangeTooLarge { fee: String, max_change: Decimal256 },
+
+    #[error("The owner cannot approve fee overrides")]
+    ApproverIsOwner {},
+
+    #[error("Fee may be at most {max} basis points")]
+    InvalidFeeBps { max: u16 },
+
+    #[error("No fees to claim")]
+    NoFeesToClaim {},
+
+    #[error("Only {available} can be sent without spending unclaimed fees")]
+    FeesUnclaimed { available: Uint128 },
+
+    #[error("Release amount exceeds the {total} it is taken from")]
+    TotalsUnderflow { total: String },
+
+    #[error("Unknown reply ID {id}")]
+    UnknownReplyId { id: u64 },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -0,0 +1,589 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal256, Event, Timestamp, Uint128, Uint256};
+
+use crate::state::{ChainJobs, FeeBounds, FeeKind, HookEvent};
+
+/// Events emitted by state-changing entry points, indexed as `wasm-<name>`
+/// where `<name>` is the snake case variant name. Attributes are named after
+/// the fields; unset optional fields are omitted and lists are comma separated.
+#[cw_serde]
+pub enum ContractEvent {
+    /// The contract was instantiated.
+    Instantiated {
+        owner: Addr,
+        palomagold_denom: String,
+    },
+    /// The contract was migrated; `steps` lists the storage migrations run.
+    Migrated {
+        from_version: String,
+        version: String,
+        steps: Vec<String>,
+    },
+    /// A chain was registered or its settings replaced; `job_created` is set
+    /// when its scheduler job was created along with it.
+    ChainRegistered {
+        chain_id: String,
+        job_id: String,
+        batch_release: bool,
+        recipient_cap: Option<Uint256>,
+        release_ttl: Option<u64>,
+        jobs: ChainJobs,
+        job_created: bool,
+    },
+    /// A token was registered or updated.
+    TokenRegistered {
+        token_key: String,
+        denom: String,
+        chain_ids: Vec<String>,
+    },
+    /// Funds were sent to an EVM chain through Skyway; `amount` is net of the
+    /// Paloma-side fee.
+    BridgeSent {
+        chain_id: String,
+        recipient: String,
+        denom: String,
+        amount: Uint128,
+    },
+    /// A release job was scheduled; `retry` is set when the nonce was scheduled before.
+    ReleaseScheduled {
+        chain_id: String,
+        recipient: String,
+        amount: Uint256,
+        nonce: Uint256,
+        job_id: String,
+        token: Option<String>,
+        retry: bool,
+    },
+    /// A scheduled release was abandoned.
+    ReleaseCancelled {
+        chain_id: String,
+        nonce: Uint256,
+        recipient: String,
+        amount: Uint256,
+        forwarded: bool,
+    },
+    /// A scheduled release was confirmed as landed.
+    ReleaseConfirmed {
+        chain_id: String,
+        nonce: Uint256,
+        recipient: String,
+        amount: Uint256,
+    },
+    /// The recipient and amount of a legacy release were recorded.
+    ReleaseBackfilled {
+        chain_id: String,
+        nonce: Uint256,
+        recipient: String,
+        amount: Uint256,
+    },
+    /// Legacy withdraw timestamps were converted into release records.
+    LegacyReleasesConverted { converted: u64, done: bool },
+    /// Funds were deposited into the contract.
+    Deposited {
+        chain_id: String,
+        sender: Addr,
+        denom: String,
+        amount: Uint128,
+    },
+    /// Skyway sends were settled.
+    SkywaySendsSettled { denom: String, amount: Uint128 },
+    /// A Skyway transaction cancellation was requested.
+    SkywayTxCancelled { transaction_id: u64 },
+    /// Addresses were added to (`denied`) or removed from the denylist.
+    DenylistUpdated {
+        denied: bool,
+        evm_addresses: Vec<String>,
+        paloma_addresses: Vec<String>,
+    },
+    /// The supply allowance of a denom was set, or removed when unset.
+    SupplyAllowanceSet {
+        denom: String,
+        allowance: Option<Uint256>,
+    },
+    /// A conversion ratio was set for a chain and epoch.
+    ConversionRatioSet {
+        chain_id: String,
+        epoch: u64,
+        ratio: Decimal256,
+        palomagold_decimals: u8,
+        evm_decimals: u8,
+    },
+    /// The migration window of a chain was set, or removed when unset.
+    MigrationWindowSet {
+        chain_id: String,
+        start: Option<Timestamp>,
+        end: Option<Timestamp>,
+        grace_period: Option<u64>,
+    },
+    /// The migration window of a chain changed status.
+    MigrationWindow { chain_id: String, status: String },
+    /// A vesting schedule was created.
+    VestingCreated {
+        chain_id: String,
+        recipient: String,
+        start: Timestamp,
+        cliff: u64,
+        duration: u64,
+        total: Uint256,
+    },
+    /// An emergency withdrawal was scheduled.
+    EmergencyWithdrawalScheduled {
+        denom: String,
+        amount: Uint128,
+        to: Addr,
+        executable_at: Timestamp,
+    },
+    /// The pending emergency withdrawal was sent.
+    EmergencyWithdrawalExecuted {
+        denom: String,
+        amount: Uint128,
+        to: Addr,
+    },
+    /// The pending emergency withdrawal was dropped.
+    EmergencyWithdrawalCancelled {
+        denom: String,
+        amount: Uint128,
+        to: Addr,
+    },
+    /// A stray denom was swept out of the contract.
+    StrayFundsSwept {
+        denom: String,
+        amount: Uint128,
+        to: Addr,
+    },
+    /// Records were imported into storage.
+    StateImported { count: u64 },
+    /// The contract was sealed against imports.
+    Sealed {},
+    /// A `set_paloma` call was scheduled on a chain.
+    PalomaSetForwarded { chain_id: String, job_id: String },
+    /// An `update_refund_wallet` call was scheduled on a chain.
+    RefundWalletUpdateForwarded {
+        chain_id: String,
+        job_id: String,
+        refund_wallet: String,
+    },
+    /// A fee update was scheduled on a chain. `fee` is `gas_fee`,
+    /// `service_fee` or `service_fee_collector`; `overridden` is set when the
+    /// value used an approved override instead of the fee bounds.
+    FeeUpdateForwarded {
+        chain_id: String,
+        job_id: String,
+        fee: String,
+        value: String,
+        overridden: bool,
+    },
+    /// Fee bounds of a chain were set, or cleared when `bounds` is unset.
+    FeeBoundsSet {
+        chain_id: String,
+        fee: FeeKind,
+        bounds: Option<FeeBounds>,
+    },
+    /// The fee override approver was set, or removed when unset.
+    FeeOverrideApproverSet { approver: Option<Addr> },
+    /// A fee value outside the bounds was approved for one update.
+    FeeOverrideApproved {
+        chain_id: String,
+        fee: FeeKind,
+        value: Uint256,
+        approver: Addr,
+    },
+    /// A new release job was announced. Retries of releases scheduled through
+    /// the old job keep using it until the rotation is finalized.
+    JobRotationAnnounced {
+        chain_id: String,
+        old_job_id: String,
+        new_job_id: String,
+        finalize_at: Timestamp,
+    },
+    /// The new release job replaced the old one for good.
+    JobRotationFinalized {
+        chain_id: String,
+        old_job_id: String,
+        new_job_id: String,
+    },
+    /// A pending job rotation was dropped.
+    JobRotationCancelled {
+        chain_id: String,
+        old_job_id: String,
+        new_job_id: String,
+    },
+    /// The Paloma-side fee was set.
+    PalomaFeeSet { bps: u16, collector: Addr },
+    /// A Paloma-side fee was kept from a bridge or deposit for `collector`.
+    FeeCharged {
+        collector: Addr,
+        denom: String,
+        amount: Uint128,
+    },
+    /// A collector claimed its accrued fees.
+    FeesClaimed { collector: Addr, amounts: Vec<Coin> },
+    /// A contract subscribed to a hook event.
+    HookAdded { event: HookEvent, contract: Addr },
+    /// A contract unsubscribed from a hook event.
+    HookRemoved { event: HookEvent, contract: Addr },
+    /// Delivering a hook event to a subscriber failed; the failure did not
+    /// affect the message that triggered it.
+    HookFailed {
+        event: HookEvent,
+        contract: Addr,
+        error: String,
+    },
+}
+
+impl ContractEvent {
+    /// Hook event subscribers of this event are notified of, if any.
+    pub fn hook_event(&self) -> Option<HookEvent> {
+        match self {
+            ContractEvent::ReleaseScheduled { .. } => Some(HookEvent::ReleaseScheduled),
+            ContractEvent::BridgeSent { .. } => Some(HookEvent::BridgeSent),
+            ContractEvent::ReleaseCancelled { .. } => Some(HookEvent::ReleaseCancelled),
+            _ => None,
+        }
+    }
+}
+
+/// Payload of a hook delivery, handed back to `reply` when it fails.
+#[cw_serde]
+pub struct HookDelivery {
+    pub event: HookEvent,
+    pub contract: Addr,
+}
+
+/// Message executed on hook subscribers.
+#[cw_serde]
+pub enum HookMsg {
+    /// Delivers an event of a subscribed hook event type.
+    MigratorHook { event: ContractEvent },
+    /// Delivers all events of a subscribed hook event type emitted by one
+    /// message, such as the releases scheduled by a `BatchRelease`.
+    MigratorHookBatch { events: Vec<ContractEvent> },
+}
+
+impl From<ContractEvent> for Event {
+    fn from(event: ContractEvent) -> Self {
+        match event {
+            ContractEvent::Instantiated {
+                owner,
+                palomagold_denom,
+            } => Event::new("instantiated")
+                .add_attribute("owner", owner)
+                .add_attribute("palomagold_denom", palomagold_denom),
+            ContractEvent::Migrated {
+                from_version,
+                version,
+                steps,
+            } => Event::new("migrated")
+                .add_attribute("from_version", from_version)
+                .add_attribute("version", version)
+                .add_attribute("steps", steps.join(",")),
+            ContractEvent::ChainRegistered {
+                chain_id,
+                job_id,
+                batch_release,
+                recipient_cap,
+                release_ttl,
+                jobs,
+                job_created,
+            } => Event::new("chain_registered")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("job_id", job_id)
+                .add_attribute("batch_release", batch_release.to_string())
+                .add_attributes(optional("recipient_cap", recipient_cap))
+                .add_attributes(optional("release_ttl", release_ttl))
+                .add_attributes(optional("release_job_id", jobs.release))
+                .add_attributes(optional("admin_job_id", jobs.admin))
+                .add_attributes(optional("fee_job_id", jobs.fee))
+                .add_attribute("job_created", job_created.to_string()),
+            ContractEvent::TokenRegistered {
+                token_key,
+                denom,
+                chain_ids,
+            } => Event::new("token_registered")
+                .add_attribute("token_key", token_key)
+                .add_attribute("denom", denom)
+                .add_attribute("chain_ids", chain_ids.join(",")),
+            ContractEvent::BridgeSent {
+                chain_id,
+                recipient,
+                denom,
+                amount,
+            } => Event::new("bridge_sent")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount),
+            ContractEvent::ReleaseScheduled {
+                chain_id,
+                recipient,
+                amount,
+                nonce,
+                job_id,
+                token,
+                retry,
+            } => Event::new("release_scheduled")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount)
+                .add_attribute("nonce", nonce)
+                .add_attribute("job_id", job_id)
+                .add_attributes(optional("token", token))
+                .add_attribute("retry", retry.to_string()),
+            ContractEvent::ReleaseCancelled {
+                chain_id,
+                nonce,
+                recipient,
+                amount,
+                forwarded,
+            } => Event::new("release_cancelled")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("nonce", nonce)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount)
+                .add_attribute("forwarded", forwarded.to_string()),
+            ContractEvent::ReleaseConfirmed {
+                chain_id,
+                nonce,
+                recipient,
+                amount,
+            } => Event::new("release_confirmed")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("nonce", nonce)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount),
+            ContractEvent::ReleaseBackfilled {
+                chain_id,
+                nonce,
+                recipient,
+                amount,
+            } => Event::new("release_backfilled")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("nonce", nonce)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount),
+            ContractEvent::LegacyReleasesConverted { converted, done } => {
+                Event::new("legacy_releases_converted")
+                    .add_attribute("converted", converted.to_string())
+                    .add_attribute("done", done.to_string())
+            }
+            ContractEvent::Deposited {
+                chain_id,
+                sender,
+                denom,
+                amount,
+            } => Event::new("deposited")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("sender", sender)
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount),
+            ContractEvent::SkywaySendsSettled { denom, amount } => {
+                Event::new("skyway_sends_settled")
+                    .add_attribute("denom", denom)
+                    .add_attribute("amount", amount)
+            }
+            ContractEvent::SkywayTxCancelled { transaction_id } => {
+                Event::new("skyway_tx_cancelled")
+                    .add_attribute("transaction_id", transaction_id.to_string())
+            }
+            ContractEvent::DenylistUpdated {
+                denied,
+                evm_addresses,
+                paloma_addresses,
+            } => Event::new("denylist_updated")
+                .add_attribute("denied", denied.to_string())
+                .add_attribute("evm_addresses", evm_addresses.join(","))
+                .add_attribute("paloma_addresses", paloma_addresses.join(",")),
+            ContractEvent::SupplyAllowanceSet { denom, allowance } => {
+                Event::new("supply_allowance_set")
+                    .add_attribute("denom", denom)
+                    .add_attributes(optional("allowance", allowance))
+            }
+            ContractEvent::ConversionRatioSet {
+                chain_id,
+                epoch,
+                ratio,
+                palomagold_decimals,
+                evm_decimals,
+            } => Event::new("conversion_ratio_set")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("epoch", epoch.to_string())
+                .add_attribute("ratio", ratio.to_string())
+                .add_attribute("palomagold_decimals", palomagold_decimals.to_string())
+                .add_attribute("evm_decimals", evm_decimals.to_string()),
+            ContractEvent::MigrationWindowSet {
+                chain_id,
+                start,
+                end,
+                grace_period,
+            } => Event::new("migration_window_set")
+                .add_attribute("chain_id", chain_id)
+                .add_attributes(optional("start", start))
+                .add_attributes(optional("end", end))
+                .add_attributes(optional("grace_period", grace_period)),
+            ContractEvent::MigrationWindow { chain_id, status } => Event::new("migration_window")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("status", status),
+            ContractEvent::VestingCreated {
+                chain_id,
+                recipient,
+                start,
+                cliff,
+                duration,
+                total,
+            } => Event::new("vesting_created")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("start", start.to_string())
+                .add_attribute("cliff", cliff.to_string())
+                .add_attribute("duration", duration.to_string())
+                .add_attribute("total", total),
+            ContractEvent::EmergencyWithdrawalScheduled {
+                denom,
+                amount,
+                to,
+                executable_at,
+            } => Event::new("emergency_withdrawal_scheduled")
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount)
+                .add_attribute("to", to)
+                .add_attribute("executable_at", executable_at.to_string()),
+            ContractEvent::EmergencyWithdrawalExecuted { denom, amount, to } => {
+                Event::new("emergency_withdrawal_executed")
+                    .add_attribute("denom", denom)
+                    .add_attribute("amount", amount)
+                    .add_attribute("to", to)
+            }
+            ContractEvent::EmergencyWithdrawalCancelled { denom, amount, to } => {
+                Event::new("emergency_withdrawal_cancelled")
+                    .add_attribute("denom", denom)
+                    .add_attribute("amount", amount)
+                    .add_attribute("to", to)
+            }
+            ContractEvent::StrayFundsSwept { denom, amount, to } => Event::new("stray_funds_swept")
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount)
+                .add_attribute("to", to),
+            ContractEvent::StateImported { count } => {
+                Event::new("state_imported").add_attribute("count", count.to_string())
+            }
+            ContractEvent::Sealed {} => Event::new("sealed"),
+            ContractEvent::PalomaSetForwarded { chain_id, job_id } => {
+                Event::new("paloma_set_forwarded")
+                    .add_attribute("chain_id", chain_id)
+                    .add_attribute("job_id", job_id)
+            }
+            ContractEvent::RefundWalletUpdateForwarded {
+                chain_id,
+                job_id,
+                refund_wallet,
+            } => Event::new("refund_wallet_update_forwarded")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("job_id", job_id)
+                .add_attribute("refund_wallet", refund_wallet),
+            ContractEvent::FeeUpdateForwarded {
+                chain_id,
+                job_id,
+                fee,
+                value,
+                overridden,
+            } => Event::new("fee_update_forwarded")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("job_id", job_id)
+                .add_attribute("fee", fee)
+                .add_attribute("value", value)
+                .add_attribute("overridden", overridden.to_string()),
+            ContractEvent::FeeBoundsSet {
+                chain_id,
+                fee,
+                bounds,
+            } => Event::new("fee_bounds_set")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("fee", fee.as_str())
+                .add_attributes(optional("min", bounds.as_ref().map(|b| b.min)))
+                .add_attributes(optional("max", bounds.as_ref().map(|b| b.max)))
+                .add_attributes(optional(
+                    "max_change",
+                    bounds.as_ref().and_then(|b| b.max_change),
+                )),
+            ContractEvent::FeeOverrideApproverSet { approver } => {
+                Event::new("fee_override_approver_set")
+                    .add_attributes(optional("approver", approver))
+            }
+            ContractEvent::FeeOverrideApproved {
+                chain_id,
+                fee,
+                value,
+                approver,
+            } => Event::new("fee_override_approved")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("fee", fee.as_str())
+                .add_attribute("value", value)
+                .add_attribute("approver", approver),
+            ContractEvent::JobRotationAnnounced {
+                chain_id,
+                old_job_id,
+                new_job_id,
+                finalize_at,
+            } => Event::new("job_rotation_announced")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("old_job_id", old_job_id)
+                .add_attribute("new_job_id", new_job_id)
+                .add_attribute("finalize_at", finalize_at.to_string()),
+            ContractEvent::JobRotationFinalized {
+                chain_id,
+                old_job_id,
+                new_job_id,
+            } => Event::new("job_rotation_finalized")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("old_job_id", old_job_id)
+                .add_attribute("new_job_id", new_job_id),
+            ContractEvent::JobRotationCancelled {
+                chain_id,
+                old_job_id,
+                new_job_id,
+            } => Event::new("job_rotation_cancelled")
+                .add_attribute("chain_id", chain_id)
+                .add_attribute("old_job_id", old_job_id)
+                .add_attribute("new_job_id", new_job_id),
+            ContractEvent::PalomaFeeSet { bps, collector } => Event::new("paloma_fee_set")
+                .add_attribute("bps", bps.to_string())
+                .add_attribute("collector", collector),
+            ContractEvent::FeeCharged {
+                collector,
+                denom,
+                amount,
+            } => Event::new("fee_charged")
+                .add_attribute("collector", collector)
+                .add_attribute("denom", denom)
+                .add_attribute("amount", amount),
+            ContractEvent::FeesClaimed { collector, amounts } => Event::new("fees_claimed")
+                .add_attribute("collector", collector)
+                .add_attribute(
+                    "amounts",
+                    amounts
+                        .iter()
+                        .map(Coin::to_string)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+            ContractEvent::HookAdded { event, contract } => Event::new("hook_added")
+                .add_attribute("event", event.as_str())
+                .add_attribute("contract", contract),
+            ContractEvent::HookRemoved { event, contract } => Event::new("hook_removed")
+                .add_attribute("event", event.as_str())
+                .add_attribute("contract", contract),
+            ContractEvent::HookFailed {
+                event,
+                contract,
+                error,
+            } => Event::new("hook_failed")
+                .add_attribute("event", event.as_str())
+                .add_attribute("contract", contract)
+                .add_attribute("error", error),
+        }
+    }
+}
+
+fn optional(key: &str, value: Option<impl ToString>) -> Option<(&str, String)> {
+    value.map(|value| (key, value.to_string()))
+}
@@ -1,7 +1,11 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, CustomMsg, Uint128, Uint256};
+use cosmwasm_std::{Addr, Binary, Coin, CustomMsg, Timestamp, Uint128, Uint256};
 
-use crate::state::ChainSetting;
+use crate::state::{
+    ChainSetting, ConversionRatio, EmergencyWithdrawal, FeeBounds, FeeKind, HookEvent, JobRotation,
+    LegacyConversion, MigrationWindow, PalomaFee, RecipientTotals, ReleaseRecord, ReleaseStatus,
+    State, SupplyTotals, TokenInfo, VestingSchedule, WindowStatus,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -9,28 +13,176 @@ pub struct InstantiateMsg {
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    // Release TTL given to chains registered without one when upgrading from 0.1
+    pub release_ttl: Option<u64>,
+    // Number of legacy withdraw timestamps converted during the migration
+    pub legacy_batch: Option<u32>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    // Register a chain, creating its scheduler job when `create_job` is set
     RegisterChain {
         chain_id: String,
         chain_setting: ChainSetting,
+        create_job: Option<NewJob>,
     },
     SendPalomaGold {
         chain_id: String,
         recipient: String,
         amount: Uint128,
+        // Registered token key, defaults to palomagold
+        token: Option<String>,
     },
     Release {
         chain_id: String,
         recipient: String,
         amount: Uint256,
         nonce: Uint256,
+        // Registered token key, defaults to palomagold
+        token: Option<String>,
+    },
+    // Register or update a token bridged alongside palomagold
+    RegisterToken {
+        token_key: String,
+        token: TokenInfo,
+    },
+    // Deposit palomagold into the contract for the migration of a chain
+    Deposit {
+        chain_id: String,
+    },
+    // Deny releases and bridges to EVM addresses and deposits from Paloma addresses
+    AddToDenylist {
+        #[serde(default)]
+        evm_addresses: Vec<String>,
+        #[serde(default)]
+        paloma_addresses: Vec<String>,
+    },
+    RemoveFromDenylist {
+        #[serde(default)]
+        evm_addresses: Vec<String>,
+        #[serde(default)]
+        paloma_addresses: Vec<String>,
+    },
+    // Switch new releases of a chain to a new job, keeping the old one for
+    // retries until the rotation is finalized after `overlap` seconds
+    AnnounceJobRotation {
+        chain_id: String,
+        new_job_id: String,
+        overlap: u64,
+        create_job: Option<NewJob>,
+    },
+    FinalizeJobRotation {
+        chain_id: String,
+    },
+    CancelJobRotation {
+        chain_id: String,
+    },
+    // Subscribe a contract to a hook event; it is executed with `HookMsg` on each event
+    AddHook {
+        event: HookEvent,
+        contract: String,
+    },
+    RemoveHook {
+        event: HookEvent,
+        contract: String,
+    },
+    // Enforce or disable (None) the supply invariant with the given allowance
+    SetSupplyAllowance {
+        token: Option<String>,
+        allowance: Option<Uint256>,
+    },
+    // Set the AAVE to palomagold conversion ratio of a chain for an epoch
+    SetConversionRatio {
+        chain_id: String,
+        epoch: u64,
+        conversion_ratio: ConversionRatio,
+    },
+    // Release the palomagold amount converted from a source AAVE amount
+    ReleaseFromAave {
+        chain_id: String,
+        recipient: String,
+        aave_amount: Uint256,
+        nonce: Uint256,
+        // Defaults to the current epoch of the chain
+        epoch: Option<u64>,
+    },
+    // Release to many recipients of a chain in a single transaction
+    BatchRelease {
+        chain_id: String,
+        releases: Vec<ReleaseItem>,
     },
     CancelTx {
         transaction_id: u64,
     },
+    // Create a vesting schedule for a recipient of a chain
+    CreateVesting {
+        chain_id: String,
+        recipient: String,
+        start: Timestamp,
+        cliff: u64,
+        duration: u64,
+        total: Uint256,
+    },
+    // Release the vested unreleased amount of a recipient. Callable by anyone.
+    ReleaseVested {
+        chain_id: String,
+        recipient: String,
+    },
+    // Abandon a scheduled release, optionally forwarding `cancel_release` to the EVM job
+    CancelRelease {
+        chain_id: String,
+        nonce: Uint256,
+        forward: bool,
+    },
+    // Mark scheduled releases as landed on the EVM chain, freeing their reserve
+    ConfirmReleases {
+        chain_id: String,
+        nonces: Vec<Uint256>,
+    },
+    // Settle Skyway sends that were delivered or cancelled
+    SettleSkywaySends {
+        token: Option<String>,
+        amount: Uint128,
+    },
+    // Continue converting legacy withdraw timestamps into release records
+    ConvertLegacyReleases {
+        limit: Option<u32>,
+    },
+    // Record the recipient and amount of legacy releases
+    BackfillReleases {
+        releases: Vec<BackfillItem>,
+    },
+    // Seed storage from records exported by another instance
+    ImportState {
+        records: Vec<StateRecord>,
+    },
+    // Refuse any further ImportState
+    Seal {},
+    // Schedule a timelocked withdrawal of contract funds to a Paloma address
+    EmergencyWithdraw {
+        denom: String,
+        amount: Uint128,
+        to: String,
+    },
+    // Send the pending emergency withdrawal once its timelock has passed
+    ExecuteEmergencyWithdraw {},
+    CancelEmergencyWithdraw {},
+    // Send the whole contract balance of a non-palomagold denom to a Paloma address
+    SweepStrayFunds {
+        denom: String,
+        to: String,
+    },
+    // Set or remove the migration window of a chain
+    SetMigrationWindow {
+        chain_id: String,
+        window: Option<MigrationWindow>,
+    },
+    // Record the current migration window status of a chain, emitting an event on change
+    SyncMigrationWindow {
+        chain_id: String,
+    },
     // Set Paloma address of a chain
     SetPaloma {
         chain_id: String,
@@ -55,24 +207,139 @@ pub enum ExecuteMsg {
         chain_id: String,
         new_service_fee: Uint256,
     },
+    // Update the service fee collector on several chains at once
+    BroadcastServiceFeeCollector {
+        chains: ChainTargets,
+        new_service_fee_collector: String,
+    },
+    // Update the service fee on several chains at once
+    BroadcastServiceFee {
+        chains: ChainTargets,
+        new_service_fee: Uint256,
+    },
+    // Set or clear the bounds of a forwarded fee on a chain
+    SetFeeBounds {
+        chain_id: String,
+        fee: FeeKind,
+        bounds: Option<FeeBounds>,
+    },
+    // Set or remove the account that approves fees outside their bounds
+    SetFeeOverrideApprover {
+        approver: Option<String>,
+    },
+    // Approve one update of a fee to a value outside its bounds
+    ApproveFeeOverride {
+        chain_id: String,
+        fee: FeeKind,
+        value: Uint256,
+    },
+    // Set the basis-point fee kept from bridges and deposits
+    SetPalomaFee {
+        bps: u16,
+        collector: String,
+    },
+    // Claim the fees accrued for the sender
+    ClaimFees {},
+}
+
+/// Chains a broadcast update is sent to.
+#[cw_serde]
+pub enum ChainTargets {
+    /// Every registered chain.
+    All,
+    /// The given chains, which must all be registered.
+    Chains(Vec<String>),
+}
+
+/// Funds accepted by an execute message.
+pub enum FundsPolicy {
+    /// No funds may be attached.
+    None,
+    /// Exactly one non-zero coin of palomagold or a registered token must be attached.
+    Palomagold,
+    /// Any funds are accepted.
+    Any,
+}
+
+impl ExecuteMsg {
+    pub fn funds_policy(&self) -> FundsPolicy {
+        match self {
+            ExecuteMsg::Deposit { .. } => FundsPolicy::Palomagold,
+            _ => FundsPolicy::None,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct BackfillItem {
+    pub chain_id: String,
+    pub nonce: Uint256,
+    pub recipient: String,
+    pub amount: Uint256,
+}
+
+#[cw_serde]
+pub struct ReleaseItem {
+    pub recipient: String,
+    pub amount: Uint256,
+    pub nonce: Uint256,
 }
 
 #[cw_serde]
 pub enum PalomaMsg {
     /// Message struct for cross-chain calls.
-    SchedulerMsg { execute_job: ExecuteJob },
+    /// Unset fields are left out, so job executions keep their 0.1 shape.
+    SchedulerMsg {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        create_job: Option<CreateJob>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        execute_job: Option<ExecuteJob>,
+    },
     SkywayMsg {
         send_tx: Option<SendTx>,
         cancel_tx: Option<CancelTx>,
     },
 }
 
+#[cw_serde]
+pub struct CreateJob {
+    pub job_id: String,
+    pub chain_type: String,
+    pub chain_reference_id: String,
+    pub definition: JobDefinition,
+    pub payload: Binary,
+    pub payload_modifiable: bool,
+    pub is_mev: bool,
+}
+
+#[cw_serde]
+pub struct JobDefinition {
+    /// Target contract ABI, as JSON.
+    pub abi: String,
+    /// Target contract address.
+    pub address: String,
+}
+
 #[cw_serde]
 pub struct ExecuteJob {
     pub job_id: String,
     pub payload: Binary,
 }
 
+/// Scheduler job created for a chain by `RegisterChain`, named after the
+/// `job_id` of the chain setting.
+#[cw_serde]
+pub struct NewJob {
+    /// Target EVM contract address.
+    pub address: String,
+    /// Target contract ABI, as JSON.
+    pub abi: String,
+    /// Defaults to `evm`.
+    pub chain_type: Option<String>,
+    #[serde(default)]
+    pub is_mev: bool,
+}
+
 #[cw_serde]
 pub struct SendTx {
     pub remote_chain_destination_address: String,
@@ -88,8 +355,82 @@ pub struct CancelTx {
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(cw2::ContractVersion)]
+    ContractInfo {},
     #[returns(BalanceResponse)]
-    PalomagoldBalance {},
+    PalomagoldBalance { token: Option<String> },
+    #[returns(TokenInfo)]
+    Token { token_key: String },
+    #[returns(TreasuryResponse)]
+    Treasury {},
+    #[returns(ConversionRatioResponse)]
+    ConversionRatio {
+        chain_id: String,
+        epoch: Option<u64>,
+    },
+    #[returns(MigrationWindowResponse)]
+    MigrationWindow { chain_id: String },
+    #[returns(VestingResponse)]
+    Vesting { chain_id: String, recipient: String },
+    #[returns(RecipientTotalsResponse)]
+    RecipientTotals {
+        chain_id: String,
+        recipient: String,
+        token: Option<String>,
+    },
+    // Releases are ordered by their nonce as a decimal string, so "10" comes before "2"
+    #[returns(RecipientHistoryResponse)]
+    RecipientHistory {
+        chain_id: String,
+        recipient: String,
+        start_after: Option<Uint256>,
+        limit: Option<u32>,
+    },
+    #[returns(SolvencyResponse)]
+    Solvency { token: Option<String> },
+    #[returns(ReleaseRecord)]
+    Release { chain_id: String, nonce: Uint256 },
+    #[returns(Option<EmergencyWithdrawal>)]
+    PendingEmergencyWithdrawal {},
+    #[returns(Option<LegacyConversion>)]
+    LegacyConversion {},
+    #[returns(ExportStateResponse)]
+    ExportState {
+        namespace: StateNamespace,
+        start_after: Option<Binary>,
+        limit: Option<u32>,
+    },
+    #[returns(DenylistResponse)]
+    Denylist {
+        kind: DenylistKind,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(HooksResponse)]
+    Hooks { event: HookEvent },
+    #[returns(JobRotationResponse)]
+    JobRotation { chain_id: String },
+    #[returns(FeeStatusResponse)]
+    FeeStatus { chain_id: String },
+    #[returns(FeeLedgerResponse)]
+    FeeLedger { collector: String },
+    #[returns(QuoteResponse)]
+    Quote {
+        chain_id: String,
+        amount: Uint128,
+        token: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub palomagold_denom: String,
+    pub sealed: bool,
+    /// Account that approves fee values outside their bounds.
+    pub fee_override_approver: Option<Addr>,
 }
 
 #[cw_serde]
@@ -97,4 +438,305 @@ pub struct BalanceResponse {
     pub balance: Uint128,
 }
 
+#[cw_serde]
+pub struct ConversionRatioResponse {
+    pub epoch: u64,
+    pub conversion_ratio: ConversionRatio,
+}
+
+#[cw_serde]
+pub struct MigrationWindowResponse {
+    pub window: Option<MigrationWindow>,
+    /// `None` when the chain has no window and is not time restricted.
+    pub status: Option<WindowStatus>,
+}
+
+#[cw_serde]
+pub struct VestingResponse {
+    pub schedule: VestingSchedule,
+    pub vested: Uint256,
+    /// Vested amount that has not been released yet.
+    pub releasable: Uint256,
+}
+
+#[cw_serde]
+pub struct RecipientTotalsResponse {
+    pub released: Uint256,
+    pub bridged: Uint128,
+    pub cap: Option<Uint256>,
+}
+
+#[cw_serde]
+pub struct RecipientRelease {
+    pub nonce: Uint256,
+    pub amount: Uint256,
+    pub scheduled_at: Timestamp,
+    pub expires_at: Option<Timestamp>,
+    pub status: ReleaseStatus,
+}
+
+#[cw_serde]
+pub struct RecipientHistoryResponse {
+    pub releases: Vec<RecipientRelease>,
+}
+
+#[cw_serde]
+pub struct SolvencyResponse {
+    pub received: Uint128,
+    pub bridged: Uint128,
+    pub released: Uint256,
+    pub released_per_chain: Vec<(String, Uint256)>,
+    pub allowance: Option<Uint256>,
+    /// Amount that can still be released before the invariant is hit.
+    pub headroom: Option<Uint256>,
+}
+
+#[cw_serde]
+pub struct TreasuryBalance {
+    /// Registered token key, `None` for palomagold.
+    pub token: Option<String>,
+    pub denom: String,
+    pub balance: Uint128,
+    /// Sent through Skyway and not yet settled.
+    pub committed: Uint128,
+    /// Scheduled for release and not yet confirmed, converted into denom
+    /// base units like supply totals.
+    pub reserved: Uint256,
+    /// Paloma-side fees accrued and not yet claimed.
+    pub fees: Uint128,
+    /// Balance not reserved for pending releases or accrued fees.
+    pub free: Uint256,
+}
+
+#[cw_serde]
+pub struct TreasuryResponse {
+    pub balances: Vec<TreasuryBalance>,
+}
+
+/// Storage namespaces paged out by `ExportState`.
+#[cw_serde]
+pub enum StateNamespace {
+    State,
+    ChainSettings,
+    WithdrawTimestamps,
+    ConversionRatios,
+    CurrentEpochs,
+    MigrationWindows,
+    WindowStatuses,
+    VestingSchedules,
+    ContractNonces,
+    RecipientTotals,
+    Releases,
+    SupplyTotals,
+    ReleasedPerChain,
+    SupplyAllowances,
+    DeniedEvmAddresses,
+    DeniedPalomaAddresses,
+    PendingEmergencyWithdrawal,
+    Tokens,
+    ReservedReleases,
+    SkywayCommitted,
+    LegacyConversion,
+    Hooks,
+    JobRotations,
+    FeeBounds,
+    ForwardedFees,
+    FeeOverrides,
+    FeeOverrideApprover,
+    PalomaFee,
+    AccruedFees,
+    FeePaidDeposits,
+}
+
+/// A single storage entry, as exported by `ExportState` and imported by `ImportState`.
+#[cw_serde]
+pub enum StateRecord {
+    State(State),
+    ChainSetting {
+        chain_id: String,
+        chain_setting: ChainSetting,
+    },
+    WithdrawTimestamp {
+        chain_id: String,
+        nonce: String,
+        timestamp: Timestamp,
+    },
+    ConversionRatio {
+        chain_id: String,
+        epoch: u64,
+        conversion_ratio: ConversionRatio,
+    },
+    CurrentEpoch {
+        chain_id: String,
+        epoch: u64,
+    },
+    MigrationWindow {
+        chain_id: String,
+        window: MigrationWindow,
+    },
+    WindowStatus {
+        chain_id: String,
+        status: WindowStatus,
+    },
+    VestingSchedule {
+        chain_id: String,
+        recipient: String,
+        schedule: VestingSchedule,
+    },
+    ContractNonce {
+        chain_id: String,
+        counter: u64,
+    },
+    RecipientTotals {
+        chain_id: String,
+        recipient: String,
+        denom: String,
+        totals: RecipientTotals,
+    },
+    Release {
+        nonce: String,
+        record: ReleaseRecord,
+    },
+    SupplyTotals {
+        denom: String,
+        totals: SupplyTotals,
+    },
+    ReleasedPerChain {
+        denom: String,
+        chain_id: String,
+        released: Uint256,
+    },
+    SupplyAllowance {
+        denom: String,
+        allowance: Uint256,
+    },
+    DeniedEvmAddress {
+        address: String,
+    },
+    DeniedPalomaAddress {
+        address: Addr,
+    },
+    PendingEmergencyWithdrawal(EmergencyWithdrawal),
+    Token {
+        token_key: String,
+        token: TokenInfo,
+    },
+    ReservedReleases {
+        denom: String,
+        reserved: Uint256,
+    },
+    SkywayCommitted {
+        denom: String,
+        committed: Uint128,
+    },
+    LegacyConversion(LegacyConversion),
+    Hook {
+        event: HookEvent,
+        contract: Addr,
+    },
+    JobRotation {
+        chain_id: String,
+        rotation: JobRotation,
+    },
+    FeeBounds {
+        chain_id: String,
+        fee: String,
+        bounds: FeeBounds,
+    },
+    ForwardedFee {
+        chain_id: String,
+        fee: String,
+        value: Uint256,
+    },
+    FeeOverride {
+        chain_id: String,
+        fee: String,
+        value: Uint256,
+    },
+    FeeOverrideApprover(Addr),
+    PalomaFee(PalomaFee),
+    AccruedFee {
+        collector: Addr,
+        denom: String,
+        amount: Uint128,
+    },
+    FeePaidDeposit {
+        denom: String,
+        amount: Uint128,
+    },
+}
+
+#[cw_serde]
+pub struct ExportStateResponse {
+    pub records: Vec<StateRecord>,
+    /// Cursor of the next page, `None` once the namespace is exhausted.
+    pub next: Option<Binary>,
+}
+
+#[cw_serde]
+pub enum DenylistKind {
+    Evm,
+    Paloma,
+}
+
+#[cw_serde]
+pub struct DenylistResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct JobRotationResponse {
+    /// Job new releases are sent through.
+    pub release_job_id: String,
+    pub rotation: Option<JobRotation>,
+}
+
+#[cw_serde]
+pub struct HooksResponse {
+    pub contracts: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct FeeStatusResponse {
+    pub approver: Option<Addr>,
+    pub gas_fee: FeeStatus,
+    pub service_fee: FeeStatus,
+}
+
+/// Quoted amounts are in base units of the denom, like supply totals.
+#[cw_serde]
+pub struct QuoteResponse {
+    /// Amount deposited.
+    pub amount: Uint128,
+    /// Kept on the Paloma side when depositing.
+    pub paloma_fee: Uint128,
+    /// Last service fee forwarded to the chain, as a fraction of 10^18.
+    pub service_fee_rate: Option<Uint256>,
+    /// Taken by the EVM contract from the bridged amount.
+    pub service_fee: Uint256,
+    /// Last gas fee forwarded to the chain, in base units of the EVM token.
+    pub gas_fee: Option<Uint256>,
+    /// `gas_fee` converted into base units of the denom, rounded up.
+    pub gas_fee_amount: Uint256,
+    /// Expected amount received after the Paloma-side, service and gas fees.
+    pub net: Uint256,
+}
+
+#[cw_serde]
+pub struct FeeLedgerResponse {
+    /// Current Paloma-side fee, if set.
+    pub fee: Option<PalomaFee>,
+    /// Fees accrued for the collector and not yet claimed.
+    pub accrued: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct FeeStatus {
+    pub bounds: Option<FeeBounds>,
+    /// Last value forwarded to the chain.
+    pub last_forwarded: Option<Uint256>,
+    /// Value approved for the next update outside the bounds.
+    pub approved_override: Option<Uint256>,
+}
+
 impl CustomMsg for PalomaMsg {}
@@ -1,8 +1,11 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-use cosmwasm_std::{Addr, Timestamp};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Decimal256, Empty, StdError, StdResult, Timestamp, Uint128, Uint256};
+use cw_storage_plus::{
+    Index, IndexList, IndexedMap, Item, Key, KeyDeserialize, Map, MultiIndex, Prefixer, PrimaryKey,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
@@ -13,8 +16,408 @@ pub struct State {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ChainSetting {
     pub job_id: String,
+    /// Send batch releases as a single `release_batch` call instead of one job per entry.
+    #[serde(default)]
+    pub batch_release: bool,
+    /// Lifetime limit on the amount released to a single recipient.
+    #[serde(default)]
+    pub recipient_cap: Option<Uint256>,
+    /// Seconds after scheduling during which a release may be retried.
+    #[serde(default)]
+    pub release_ttl: Option<u64>,
+    /// Jobs used instead of `job_id` for some purposes.
+    #[serde(default)]
+    pub jobs: ChainJobs,
+}
+
+impl ChainSetting {
+    /// Job that remote calls made for `purpose` are sent through.
+    pub fn job(&self, purpose: JobPurpose) -> &str {
+        let job = match purpose {
+            JobPurpose::Release => &self.jobs.release,
+            JobPurpose::Admin => &self.jobs.admin,
+            JobPurpose::Fee => &self.jobs.fee,
+        };
+        job.as_deref().unwrap_or(&self.job_id)
+    }
+}
+
+/// Job IDs by purpose. Unset purposes fall back to the default job.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct ChainJobs {
+    /// Releases, including batch, AAVE and vested releases.
+    pub release: Option<String>,
+    /// `set_paloma` and `update_refund_wallet`.
+    pub admin: Option<String>,
+    /// Gas fee, service fee and service fee collector updates.
+    pub fee: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPurpose {
+    Release,
+    Admin,
+    Fee,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConversionRatio {
+    /// Palomagold base units received per base unit of AAVE.
+    pub ratio: Decimal256,
+    /// Decimals of the palomagold Cosmos denom.
+    pub palomagold_decimals: u8,
+    /// Decimals of the token released on the EVM chain.
+    pub evm_decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrationWindow {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// Seconds after `end` during which releases are still accepted.
+    pub grace_period: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowStatus {
+    NotStarted,
+    Open,
+    Grace,
+    Closed,
+}
+
+impl MigrationWindow {
+    pub fn status(&self, now: Timestamp) -> WindowStatus {
+        if now < self.start {
+            WindowStatus::NotStarted
+        } else if now < self.end {
+            WindowStatus::Open
+        } else if now < self.end.plus_seconds(self.grace_period.unwrap_or_default()) {
+            WindowStatus::Grace
+        } else {
+            WindowStatus::Closed
+        }
+    }
+}
+
+impl WindowStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WindowStatus::NotStarted => "not_started",
+            WindowStatus::Open => "open",
+            WindowStatus::Grace => "grace",
+            WindowStatus::Closed => "closed",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VestingSchedule {
+    pub start: Timestamp,
+    /// Seconds after `start` before anything vests.
+    pub cliff: u64,
+    /// Seconds after `start` over which `total` vests linearly.
+    pub duration: u64,
+    pub total: Uint256,
+    /// Amount already scheduled for release.
+    pub released: Uint256,
+}
+
+impl VestingSchedule {
+    pub fn vested(&self, now: Timestamp) -> Uint256 {
+        if now < self.start.plus_seconds(self.cliff) {
+            return Uint256::zero();
+        }
+        let elapsed = now.seconds() - self.start.seconds();
+        if elapsed >= self.duration {
+            self.total
+        } else {
+            self.total.multiply_ratio(elapsed, self.duration)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct RecipientTotals {
+    pub released: Uint256,
+    pub bridged: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReleaseRecord {
+    pub chain_id: String,
+    pub recipient: String,
+    pub amount: Uint256,
+    pub scheduled_at: Timestamp,
+    /// Retries are refused from this time on.
+    pub expires_at: Option<Timestamp>,
+    pub status: ReleaseStatus,
+    /// Registered token key, `None` for palomagold.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Job the release was scheduled through, `None` for releases scheduled
+    /// before jobs were recorded.
+    #[serde(default)]
+    pub job_id: Option<String>,
+    /// `amount` in base units of the Cosmos denom, as counted in supply
+    /// totals and reserves. `None` for releases not counted in them.
+    #[serde(default)]
+    pub denom_amount: Option<Uint256>,
+    /// Scheduled by `ReleaseVested` on a contract nonce.
+    #[serde(default)]
+    pub vested: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseStatus {
+    Scheduled,
+    Abandoned,
+    /// Confirmed by the owner as landed on the EVM chain.
+    Completed,
+    /// Released before release records were kept. The recipient is empty and
+    /// the amount zero until they are backfilled.
+    Legacy,
+}
+
+impl ReleaseRecord {
+    /// Record of a nonce released before release records were kept.
+    pub fn legacy(chain_id: String, scheduled_at: Timestamp) -> Self {
+        ReleaseRecord {
+            chain_id,
+            recipient: String::new(),
+            amount: Uint256::zero(),
+            scheduled_at,
+            expires_at: None,
+            status: ReleaseStatus::Legacy,
+            token: None,
+            job_id: None,
+            denom_amount: None,
+            vested: false,
+        }
+    }
+}
+
+pub struct ReleaseIndexes<'a> {
+    /// Releases by (chain_id, recipient).
+    pub recipient: MultiIndex<'a, (String, String), ReleaseRecord, (String, String)>,
+}
+
+impl IndexList<ReleaseRecord> for ReleaseIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<ReleaseRecord>> + '_> {
+        let v: Vec<&dyn Index<ReleaseRecord>> = vec![&self.recipient];
+        Box::new(v.into_iter())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct LegacyConversion {
+    /// Last `WITHDRAW_TIMESTAMP` key converted.
+    pub last: Option<(String, String)>,
+    pub converted: u64,
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct SupplyTotals {
+    /// Palomagold deposited into the contract.
+    pub received: Uint128,
+    /// Palomagold bridged out through Skyway.
+    pub bridged: Uint128,
+    /// Amount released on all chains, in base units of the denom.
+    pub released: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EmergencyWithdrawal {
+    pub denom: String,
+    pub amount: Uint128,
+    pub to: Addr,
+    pub executable_at: Timestamp,
+}
+
+/// Staged switch of the release job of a chain. New releases go through
+/// `new_job_id` right away, while retries of releases scheduled through
+/// `old_job_id` keep using it until the rotation is finalized.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct JobRotation {
+    pub old_job_id: String,
+    pub new_job_id: String,
+    pub announced_at: Timestamp,
+    /// The rotation can be finalized from this time on.
+    pub finalize_at: Timestamp,
+}
+
+/// Fees forwarded to the EVM contract that are checked against bounds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeKind {
+    GasFee,
+    ServiceFee,
+}
+
+impl FeeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeKind::GasFee => "gas_fee",
+            FeeKind::ServiceFee => "service_fee",
+        }
+    }
+}
+
+/// Limits on the values of a fee forwarded to a chain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeBounds {
+    pub min: Uint256,
+    pub max: Uint256,
+    /// Largest change from the last forwarded value, as a ratio of it.
+    #[serde(default)]
+    pub max_change: Option<Decimal256>,
+}
+
+/// Fee kept on the Paloma side of bridges and deposits for a collector.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PalomaFee {
+    /// Share of each bridge or deposit in basis points.
+    pub bps: u16,
+    pub collector: Addr,
+}
+
+impl PalomaFee {
+    /// Fee kept from `amount`, rounded down.
+    pub fn fee(&self, amount: Uint128) -> Uint128 {
+        amount.multiply_ratio(self.bps, 10_000u128)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TokenInfo {
+    /// Cosmos denom of the token on Paloma.
+    pub denom: String,
+    /// EVM token by chain ID.
+    pub chains: BTreeMap<String, EvmToken>,
+    /// Decimals of the Cosmos denom. When set, releases are converted from
+    /// the decimals of the EVM token into denom base units for supply totals.
+    #[serde(default)]
+    pub decimals: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EvmToken {
+    pub address: String,
+    pub decimals: u8,
+}
+
+/// Events downstream contracts can subscribe to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    ReleaseScheduled,
+    BridgeSent,
+    ReleaseCancelled,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::ReleaseScheduled => "release_scheduled",
+            HookEvent::BridgeSent => "bridge_sent",
+            HookEvent::ReleaseCancelled => "release_cancelled",
+        }
+    }
+}
+
+impl<'a> PrimaryKey<'a> for HookEvent {
+    type Prefix = ();
+    type SubPrefix = ();
+    type Suffix = Self;
+    type SuperSuffix = Self;
+
+    fn key(&self) -> Vec<Key<'_>> {
+        vec![Key::Ref(self.as_str().as_bytes())]
+    }
+}
+
+impl<'a> Prefixer<'a> for HookEvent {
+    fn prefix(&self) -> Vec<Key<'_>> {
+        vec![Key::Ref(self.as_str().as_bytes())]
+    }
+}
+
+impl KeyDeserialize for HookEvent {
+    type Output = HookEvent;
+    const KEY_ELEMS: u16 = 1;
+
+    fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        match value.as_slice() {
+            b"release_scheduled" => Ok(HookEvent::ReleaseScheduled),
+            b"bridge_sent" => Ok(HookEvent::BridgeSent),
+            b"release_cancelled" => Ok(HookEvent::ReleaseCancelled),
+            _ => Err(StdError::parse_err("HookEvent", "unknown hook event")),
+        }
+    }
 }
 
 pub const STATE: Item<State> = Item::new("state");
 pub const CHAIN_SETTINGS: Map<String, ChainSetting> = Map::new("chain_settings");
 pub const WITHDRAW_TIMESTAMP: Map<(String, String), Timestamp> = Map::new("withdraw_timestamp");
+pub const CONVERSION_RATIOS: Map<(String, u64), ConversionRatio> = Map::new("conversion_ratios");
+pub const CURRENT_EPOCH: Map<String, u64> = Map::new("current_epoch");
+pub const MIGRATION_WINDOWS: Map<String, MigrationWindow> = Map::new("migration_windows");
+pub const WINDOW_STATUS: Map<String, WindowStatus> = Map::new("window_status");
+pub const VESTING_SCHEDULES: Map<(String, String), VestingSchedule> = Map::new("vesting_schedules");
+pub const CONTRACT_NONCE: Map<String, u64> = Map::new("contract_nonce");
+/// Totals keyed by (chain_id, recipient, denom).
+pub const RECIPIENT_TOTALS: Map<(String, String, String), RecipientTotals> =
+    Map::new("recipient_totals");
+
+/// Release records keyed by (chain_id, nonce).
+pub fn releases<'a>() -> IndexedMap<(String, String), ReleaseRecord, ReleaseIndexes<'a>> {
+    let indexes = ReleaseIndexes {
+        recipient: MultiIndex::new(
+            |_pk, record| (record.chain_id.clone(), record.recipient.clone()),
+            "releases",
+            "releases__recipient",
+        ),
+    };
+    IndexedMap::new("releases", indexes)
+}
+/// Supply totals keyed by denom.
+pub const SUPPLY_TOTALS: Map<String, SupplyTotals> = Map::new("supply_totals");
+/// Released amounts in denom base units keyed by (denom, chain_id).
+pub const RELEASED_PER_CHAIN: Map<(String, String), Uint256> = Map::new("released_per_chain");
+/// When set for a denom, its releases may not exceed the bridged amount plus this allowance.
+pub const SUPPLY_ALLOWANCE: Map<String, Uint256> = Map::new("supply_allowance");
+/// Denied EVM addresses, in canonical lowercase `0x` form.
+pub const DENIED_EVM_ADDRESSES: Map<String, Empty> = Map::new("denied_evm_addresses");
+pub const DENIED_PALOMA_ADDRESSES: Map<&Addr, Empty> = Map::new("denied_paloma_addresses");
+pub const PENDING_EMERGENCY_WITHDRAWAL: Item<EmergencyWithdrawal> =
+    Item::new("pending_emergency_withdrawal");
+pub const TOKENS: Map<String, TokenInfo> = Map::new("tokens");
+/// Amounts of scheduled, unconfirmed releases in denom base units keyed by denom.
+pub const RESERVED_RELEASES: Map<String, Uint256> = Map::new("reserved_releases");
+/// Amounts of Skyway sends not yet settled keyed by denom.
+pub const SKYWAY_COMMITTED: Map<String, Uint128> = Map::new("skyway_committed");
+/// Progress of converting `WITHDRAW_TIMESTAMP` entries into release records.
+pub const LEGACY_CONVERSION: Item<LegacyConversion> = Item::new("legacy_conversion");
+/// Once set, `ImportState` is refused.
+pub const SEALED: Item<bool> = Item::new("sealed");
+/// Contracts notified of each hook event.
+pub const HOOKS: Map<(HookEvent, &Addr), Empty> = Map::new("hooks");
+/// Pending release job rotations keyed by chain ID.
+pub const JOB_ROTATIONS: Map<String, JobRotation> = Map::new("job_rotations");
+/// Fee bounds keyed by (chain_id, fee).
+pub const FEE_BOUNDS: Map<(String, String), FeeBounds> = Map::new("fee_bounds");
+/// Last fee values forwarded keyed by (chain_id, fee).
+pub const FORWARDED_FEES: Map<(String, String), Uint256> = Map::new("forwarded_fees");
+/// Fee values approved outside their bounds keyed by (chain_id, fee); each is used once.
+pub const FEE_OVERRIDES: Map<(String, String), Uint256> = Map::new("fee_overrides");
+/// Account that approves fee values outside their bounds.
+pub const FEE_OVERRIDE_APPROVER: Item<Addr> = Item::new("fee_override_approver");
+pub const PALOMA_FEE: Item<PalomaFee> = Item::new("paloma_fee");
+/// Paloma-side fees not yet claimed keyed by (collector, denom).
+pub const ACCRUED_FEES: Map<(&Addr, String), Uint128> = Map::new("accrued_fees");
+/// Deposited funds that paid the Paloma-side fee and were not sent out yet, by denom.
+pub const FEE_PAID_DEPOSITS: Map<String, Uint128> = Map::new("fee_paid_deposits");